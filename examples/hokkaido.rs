@@ -1,36 +1,17 @@
-use name_engine::{Name, NameGeneratorBuilder};
+use name_engine::{dataset::DatasetReader, NameGeneratorBuilder};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 
 fn main() {
     let csv_file = include_str!("assets/hokkaido.csv");
 
-    let place_names = csv_file
-        .lines()
-        .filter_map(|line| {
-            let split = line.split(',');
-            let phrases = split
-                .last()
-                .unwrap()
-                .split(':')
-                .map(|phrase| {
-                    let mut split = phrase.split('_');
-                    (split.next().unwrap(), split.next().unwrap())
-                })
-                .collect::<Vec<(&str, &str)>>();
-            if let Ok(name) = Name::new(phrases) {
-                Some(name)
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<Name>>();
-
-    let generator = NameGeneratorBuilder::new()
-        .bulk_add_names(place_names)
-        .build();
+    let generator = DatasetReader::new(csv_file.as_bytes())
+        .add_to_builder(NameGeneratorBuilder::new())
+        .unwrap()
+        .build()
+        .unwrap();
     let mut rng: StdRng = SeedableRng::seed_from_u64(0);
-    (0..100).for_each(|_| {
-        let name = generator.generate(|| rng.gen());
-        println!("{} {}", name.0, name.1);
+    let names = generator.iter(|| rng.gen()).take_unique(100).unwrap();
+    names.iter().for_each(|name| {
+        println!("{} {}", name.content(), name.script());
     });
 }
@@ -1,4 +1,4 @@
-use name_engine::{Name, NameGeneratorBuilder};
+use name_engine::{Name, NameGeneratorBuilder, Smoothing};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 
 fn main() {
@@ -166,12 +166,15 @@ fn main() {
     .map(|name| Name::new(name.to_vec()).unwrap())
     .collect::<Vec<Name>>();
 
+    // the dataset is small, so the unseen connections are smoothed
     let generator = NameGeneratorBuilder::new()
+        .smoothing(Smoothing::WittenBell)
         .bulk_add_names(place_names)
-        .build();
+        .build()
+        .unwrap();
     let mut rng: StdRng = SeedableRng::seed_from_u64(0);
-    (0..100).for_each(|_| {
-        let name = generator.generate(|| rng.gen());
-        println!("{} {}", name.0, name.1);
+    let names = generator.iter(|| rng.gen()).take_unique(100).unwrap();
+    names.iter().for_each(|name| {
+        println!("{} {}", name.content(), name.script());
     });
 }
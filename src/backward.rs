@@ -0,0 +1,244 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::connection::{self, reversed, PhonemeKey};
+use crate::draw::{Draw, FnDraw};
+use crate::options::preferred;
+use crate::{GeneratedName, GenerationOptions, NameError, NameGenerator, SyllableInfo, ToRestore};
+
+impl NameGenerator {
+    /// Generate a name ending with the syllable whose letter is `ending`, growing it backward under `options`.
+    ///  The letter is compared ignoring the case. The syllables are counted from the end for `min_syllables` and `max_syllables`.
+    ///  The syllables are grown through the backward phonetic join until a name start, regardless of `Model` and `Termination`,
+    ///  and `NameError::UnsupportedConstraints` is returned if `options` has constraints.
+    ///
+    /// Example: generator.generate_backward("minster", &GenerationOptions::new(), || rng.gen())
+    pub fn generate_backward(
+        &self,
+        ending: &str,
+        options: &GenerationOptions,
+        mut rand_fn: impl FnMut() -> f64,
+    ) -> Result<GeneratedName<'_>, NameError> {
+        options.validate()?;
+        if options.constrains() {
+            return Err(NameError::UnsupportedConstraints);
+        }
+        let draw = &mut FnDraw(&mut rand_fn);
+        let list = self.matching(self.final_syllables.iter().copied(), ending)?;
+        let (syllable, probability) = self.choose(
+            preferred(list.iter().collect(), |syllable| {
+                options.prefers(1, syllable.2)
+            }),
+            false,
+            options,
+            None,
+            draw,
+        )?;
+        let mut syllable_info = vec![self.syllable_info(syllable, probability, None)];
+        self.continue_backward(&mut syllable_info, syllable.2, options, draw)?;
+        syllable_info.reverse();
+        Ok(self.joined(syllable_info))
+    }
+
+    /// Generate a name containing the syllable whose letter is `middle`, growing it in both directions under `options`.
+    ///  The letter is compared ignoring the case. The syllables after `middle` follow `Model` and `Termination`,
+    ///  while the ones before it are grown as `generate_backward` does.
+    ///  `NameError::UnsupportedConstraints` is returned if `options` has constraints.
+    ///
+    /// Example: generator.generate_around("bridge", &GenerationOptions::new(), || rng.gen())
+    pub fn generate_around(
+        &self,
+        middle: &str,
+        options: &GenerationOptions,
+        mut rand_fn: impl FnMut() -> f64,
+    ) -> Result<GeneratedName<'_>, NameError> {
+        options.validate()?;
+        if options.constrains() {
+            return Err(NameError::UnsupportedConstraints);
+        }
+        let draw = &mut FnDraw(&mut rand_fn);
+        let occurrences = self.names.iter().enumerate().flat_map(|(ipn, name)| {
+            let len = name.syllables().len();
+            (0..len)
+                .filter(move |ipc| !name.flags_of_syllable(*ipc).separator)
+                .map(move |ipc| (ipn, ipc, ipc + 1 != len))
+        });
+        let list = self.matching(occurrences, middle)?;
+        let (syllable, probability) = self.choose(
+            preferred(list.iter().collect(), |syllable| {
+                options.prefers(1, syllable.2)
+            }),
+            false,
+            options,
+            None,
+            draw,
+        )?;
+        let mut syllable_info = vec![self.syllable_info(syllable, probability, None)];
+        self.continue_forward(&mut syllable_info, syllable.2, options, None, draw)?;
+        // the middle syllable is placed at the end to continue backward
+        syllable_info.reverse();
+        self.continue_backward(&mut syllable_info, syllable.1 != 0, options, draw)?;
+        syllable_info.reverse();
+        Ok(self.joined(syllable_info))
+    }
+
+    /// The syllables in `list` whose letters are `letter` ignoring the case.
+    ///  An error is returned if there is none.
+    fn matching(
+        &self,
+        list: impl Iterator<Item = (usize, usize, ToRestore)>,
+        letter: &str,
+    ) -> Result<Vec<(usize, usize, ToRestore)>, NameError> {
+        let letter = letter.to_lowercase();
+        let list = list
+            .filter(|(ipn, ipc, _)| self.names[*ipn].syllables()[*ipc].0.to_lowercase() == letter)
+            .collect::<Vec<_>>();
+        if list.is_empty() {
+            return Err(NameError::NoSuchSyllable(letter));
+        }
+        Ok(list)
+    }
+
+    /// Append the preceding syllables to `syllable_info`, which is in the reversed order, until the name starts, if `to_extend` is set.
+    fn continue_backward(
+        &self,
+        syllable_info: &mut Vec<SyllableInfo>,
+        mut to_extend: ToRestore,
+        options: &GenerationOptions,
+        draw: &mut dyn Draw,
+    ) -> Result<(), NameError> {
+        while to_extend {
+            if syllable_info.len() >= options.syllable_limit {
+                return Err(NameError::SyllableLimitExceeded(options.syllable_limit));
+            }
+            let last = &syllable_info[syllable_info.len() - 1];
+            let (syllable, probability) = self.query_previous(
+                (last.name_index, last.syllable_index),
+                syllable_info.len() + 1,
+                options,
+                draw,
+            )?;
+            syllable_info.push(self.syllable_info(syllable, probability, None));
+            to_extend = syllable.2;
+        }
+        Ok(())
+    }
+
+    /// Choose the syllable preceding the syllable `(ipn, ipc)` at the position `count` from the end.
+    fn query_previous(
+        &self,
+        (ipn, ipc): (usize, usize),
+        count: usize,
+        options: &GenerationOptions,
+        draw: &mut dyn Draw,
+    ) -> Result<(&(usize, usize, ToRestore), f64), NameError> {
+        let p0 = draw.next_f64()?;
+        // a word is ended before a separator
+        if self.names[ipn].flags_of_syllable(ipc).separator {
+            let list = preferred(self.final_syllables.iter().collect(), |syllable| {
+                options.prefers(count, syllable.2)
+            });
+            return self.choose(list, false, options, None, draw);
+        }
+        let connections = self.backward_connections((ipn, ipc), count, options)?;
+        let (connection_syllable, connection_probability) =
+            connection::extract_backward(&connections, p0);
+        let preceding_syllable_list = self.preceding_list(&connection_syllable, count, options)?;
+        let (preceding_syllable, probability) =
+            self.choose(preceding_syllable_list, false, options, None, draw)?;
+        Ok((preceding_syllable, connection_probability * probability))
+    }
+
+    /// The backward phonetic connections from the syllable `(ipn, ipc)` to the syllable at the position `count` from the end,
+    ///  with the probabilities reshaped by `options`. The keys are reversed.
+    ///  The connections to the syllables preferred by `options` are used if any.
+    fn backward_connections(
+        &self,
+        (ipn, ipc): (usize, usize),
+        count: usize,
+        options: &GenerationOptions,
+    ) -> Result<Vec<(&PhonemeKey, f64)>, NameError> {
+        let flags = self.names[ipn].flags_of_syllable(ipc);
+        let allowed = |key: &PhonemeKey| flags.allows_previous(key);
+
+        let outgoing_phonemes = self.names[ipn].phonemes_of_syllable(ipc);
+        let reversed_phonemes = reversed(outgoing_phonemes);
+        let mut candidates = self.backward_conn.distribution(&reversed_phonemes, |key| {
+            allowed(key)
+                && self.preceding_tree.get(&reversed(key)).is_some_and(|list| {
+                    list.iter()
+                        .any(|i| options.prefers(count, self.preceding_syllables[*i].2))
+                })
+        });
+        if candidates.is_empty() {
+            candidates = self.backward_conn.distribution(&reversed_phonemes, allowed);
+        }
+        if candidates.is_empty() {
+            return Err(NameError::NoPhoneticConnection(outgoing_phonemes.concat()));
+        }
+        Ok(options.reshape(candidates))
+    }
+
+    /// The preceding syllables ending with `key` preferred at the position `count` from the end.
+    fn preceding_list(
+        &self,
+        key: &PhonemeKey,
+        count: usize,
+        options: &GenerationOptions,
+    ) -> Result<Vec<&(usize, usize, ToRestore)>, NameError> {
+        Ok(preferred(
+            self.preceding_tree
+                .get(key)
+                .ok_or_else(|| NameError::NoPrecedingSyllable(key.concat()))?
+                .iter()
+                .map(|i| &self.preceding_syllables[*i])
+                .collect(),
+            |syllable| options.prefers(count, syllable.2),
+        ))
+    }
+
+    /// The name of `syllable_info`, with the joins filled in as in the forward generation.
+    fn joined(&self, mut syllable_info: Vec<SyllableInfo>) -> GeneratedName<'_> {
+        for i in 0..syllable_info.len() {
+            let previous_separator = i > 0 && {
+                let previous = &syllable_info[i - 1];
+                self.names[previous.name_index]
+                    .flags_of_syllable(previous.syllable_index)
+                    .separator
+            };
+            let info = &mut syllable_info[i];
+            info.join = (i > 0 && !previous_separator).then(|| {
+                self.conn.outgoing_key(
+                    self.names[info.name_index].phonemes_of_syllable(info.syllable_index),
+                )
+            });
+        }
+        GeneratedName::new(&self.names, syllable_info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sample_generator, GenerationOptions, NameError};
+
+    #[test]
+    fn constraints_are_rejected() {
+        let generator = sample_generator();
+        let options = GenerationOptions::new().ends_with("ley");
+        assert!(matches!(
+            generator.generate_backward("ford", &options, || 0.5),
+            Err(NameError::UnsupportedConstraints)
+        ));
+        assert!(matches!(
+            generator.generate_around("ox", &options, || 0.5),
+            Err(NameError::UnsupportedConstraints)
+        ));
+        let name = generator
+            .generate_backward("ford", &GenerationOptions::new(), || 0.5)
+            .unwrap();
+        assert!(name.content().ends_with("ford"));
+    }
+}
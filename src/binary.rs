@@ -0,0 +1,251 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! The compact binary format of a built `NameGenerator`.
+//!
+//! The file starts with the magic bytes `NENG` and the format version as a little-endian `u32`.
+//! The rest is the fields of the generator, where integers are little-endian `u64`, floats are their IEEE 754 bits,
+//! strings are length-prefixed UTF-8, and sequences are length-prefixed lists of their elements.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{Read, Write};
+
+use crate::NameError;
+
+/// The magic bytes at the beginning of the file.
+pub(crate) const MAGIC: &[u8; 4] = b"NENG";
+/// The version of the format. Files of the other versions are rejected.
+pub const FORMAT_VERSION: u32 = 1;
+
+pub(crate) fn invalid(reason: &str) -> NameError {
+    NameError::InvalidFormat(reason.to_string())
+}
+
+/// The value that can be written in the binary format.
+pub(crate) trait Encode {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError>;
+}
+
+/// The value that can be read from the binary format.
+pub(crate) trait Decode: Sized {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError>;
+}
+
+fn read_bytes<const N: usize>(reader: &mut dyn Read) -> Result<[u8; N], NameError> {
+    let mut buf = [0; N];
+    reader.read_exact(&mut buf).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => invalid("unexpected end of file"),
+        _ => NameError::Io(e),
+    })?;
+    Ok(buf)
+}
+
+pub(crate) fn write_header(writer: &mut dyn Write) -> Result<(), NameError> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    Ok(())
+}
+
+pub(crate) fn read_header(reader: &mut dyn Read) -> Result<(), NameError> {
+    if &read_bytes::<4>(reader)? != MAGIC {
+        return Err(invalid("not a name-engine file"));
+    }
+    let version = u32::from_le_bytes(read_bytes(reader)?);
+    if version != FORMAT_VERSION {
+        return Err(NameError::IncompatibleVersion {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+    Ok(())
+}
+
+impl Encode for bool {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        writer.write_all(&[*self as u8])?;
+        Ok(())
+    }
+}
+
+impl Decode for bool {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        match read_bytes::<1>(reader)? {
+            [0] => Ok(false),
+            [1] => Ok(true),
+            _ => Err(invalid("invalid boolean")),
+        }
+    }
+}
+
+impl Encode for usize {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        writer.write_all(&(*self as u64).to_le_bytes())?;
+        Ok(())
+    }
+}
+
+impl Decode for usize {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        usize::try_from(u64::from_le_bytes(read_bytes(reader)?))
+            .map_err(|_| invalid("integer out of range"))
+    }
+}
+
+impl Encode for f64 {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        writer.write_all(&self.to_bits().to_le_bytes())?;
+        Ok(())
+    }
+}
+
+impl Decode for f64 {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Ok(f64::from_bits(u64::from_le_bytes(read_bytes(reader)?)))
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.len().encode(writer)?;
+        writer.write_all(self.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl Decode for String {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        let len = usize::decode(reader)?;
+        let mut buf = vec![];
+        reader.take(len as u64).read_to_end(&mut buf)?;
+        if buf.len() != len {
+            return Err(invalid("unexpected end of file"));
+        }
+        String::from_utf8(buf).map_err(|_| invalid("invalid UTF-8 string"))
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.len().encode(writer)?;
+        self.iter().try_for_each(|e| e.encode(writer))
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        let len = usize::decode(reader)?;
+        // the length is not trusted for the allocation
+        let mut vec = Vec::with_capacity(len.min(1024));
+        for _ in 0..len {
+            vec.push(T::decode(reader)?);
+        }
+        Ok(vec)
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.0.encode(writer)?;
+        self.1.encode(writer)
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Ok((A::decode(reader)?, B::decode(reader)?))
+    }
+}
+
+impl<A: Encode, B: Encode, C: Encode> Encode for (A, B, C) {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.0.encode(writer)?;
+        self.1.encode(writer)?;
+        self.2.encode(writer)
+    }
+}
+
+impl<A: Decode, B: Decode, C: Decode> Decode for (A, B, C) {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Ok((A::decode(reader)?, B::decode(reader)?, C::decode(reader)?))
+    }
+}
+
+impl<K: Encode + Ord, V: Encode> Encode for HashMap<K, V> {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        // sorted by the keys to write the same bytes for the same generator
+        let mut entries = self.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries.len().encode(writer)?;
+        entries.iter().try_for_each(|(k, v)| {
+            k.encode(writer)?;
+            v.encode(writer)
+        })
+    }
+}
+
+impl<K: Decode + Eq + Hash, V: Decode> Decode for HashMap<K, V> {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Ok(Vec::<(K, V)>::decode(reader)?.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sample_generator, GenerationOptions, NameError, NameGenerator, FORMAT_VERSION};
+
+    fn saved() -> Vec<u8> {
+        let mut bytes = vec![];
+        sample_generator().save(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = saved();
+        let mut loaded = vec![];
+        NameGenerator::load(bytes.as_slice())
+            .unwrap()
+            .save(&mut loaded)
+            .unwrap();
+        assert_eq!(bytes, loaded);
+    }
+
+    #[test]
+    fn same_seeded_names_after_load() {
+        let generator = sample_generator();
+        let loaded = NameGenerator::load(saved().as_slice()).unwrap();
+        let options = GenerationOptions::default();
+        for index in 0..100 {
+            let name = generator.generate_seeded(&options, 1234, index).unwrap();
+            let loaded_name = loaded.generate_seeded(&options, 1234, index).unwrap();
+            assert_eq!(name.content(), loaded_name.content());
+            assert_eq!(name.script(), loaded_name.script());
+        }
+    }
+
+    #[test]
+    fn wrong_version() {
+        let mut bytes = saved();
+        bytes[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(matches!(
+            NameGenerator::load(bytes.as_slice()),
+            Err(NameError::IncompatibleVersion { found, supported })
+                if found == FORMAT_VERSION + 1 && supported == FORMAT_VERSION
+        ));
+    }
+
+    #[test]
+    fn truncated_file() {
+        let bytes = saved();
+        for len in [0, 6, 8, bytes.len() / 2, bytes.len() - 1] {
+            assert!(matches!(
+                NameGenerator::load(&bytes[..len]),
+                Err(NameError::InvalidFormat(_))
+            ));
+        }
+    }
+}
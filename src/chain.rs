@@ -0,0 +1,167 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::binary::{invalid, Decode, Encode};
+use crate::constraint::Progress;
+use crate::options::preferred;
+use crate::{Candidate, GenerationOptions, NameError, NameGenerator, PhonemeKey, ToRestore};
+
+/// The model of the transitions between the syllables.
+///
+/// Example: NameGeneratorBuilder::new().model(Model::SyllableChain { weight: 0.5 })
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Model {
+    /// The next syllable is chosen by the phonetic connection from the previous syllable,
+    ///  and then among the syllables starting with the connected phonemes regardless of the previous syllable.
+    #[default]
+    PhoneticJoin,
+    /// The phonetic join interpolated with the transitions between the syllables (letter and phonics) in the dataset.
+    ///  The next syllable is chosen by the transitions with the probability `weight` in `[0, 1)`,
+    ///  and by the phonetic join otherwise. The phonetic join alone is used after the syllables with no transition.
+    ///  The backward generation always uses the phonetic join.
+    SyllableChain { weight: f64 },
+}
+
+impl Model {
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            Self::PhoneticJoin => true,
+            // the weight 1 would only reproduce the syllable sequences in the dataset
+            Self::SyllableChain { weight } => (0.0..1.0).contains(weight),
+        }
+    }
+
+    /// The weight of the transitions between the syllables in the interpolation.
+    pub(crate) fn chain_weight(&self) -> f64 {
+        match self {
+            Self::PhoneticJoin => 0.0,
+            Self::SyllableChain { weight } => *weight,
+        }
+    }
+}
+
+impl Encode for Model {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        match self {
+            Self::PhoneticJoin => false.encode(writer),
+            Self::SyllableChain { weight } => {
+                true.encode(writer)?;
+                weight.encode(writer)
+            }
+        }
+    }
+}
+
+impl Decode for Model {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        let model = if bool::decode(reader)? {
+            Self::SyllableChain {
+                weight: f64::decode(reader)?,
+            }
+        } else {
+            Self::PhoneticJoin
+        };
+        if !model.is_valid() {
+            return Err(invalid("invalid model"));
+        }
+        Ok(model)
+    }
+}
+
+impl NameGenerator {
+    /// The indices of the outgoing syllables that follow the syllable `(ipn, ipc)` in the dataset,
+    ///  if the transitions between the syllables are used after it.
+    pub(crate) fn successors(&self, (ipn, ipc): (usize, usize)) -> Option<&Vec<usize>> {
+        if self.model.chain_weight() == 0.0 {
+            return None;
+        }
+        self.successor_tree.get(&self.names[ipn].syllables()[ipc])
+    }
+
+    /// The outgoing syllables following the syllable `(ipn, ipc)` at the position `count` with their probabilities,
+    ///  interpolating the phonetic join and the transitions between the syllables.
+    ///  The syllables are steered by `progress` and reshaped by `options`, and the ones preferred by `options` are used if any.
+    pub(crate) fn interpolated(
+        &self,
+        (ipn, ipc): (usize, usize),
+        count: usize,
+        options: &GenerationOptions,
+        progress: Progress,
+    ) -> Result<Vec<Candidate<'_>>, NameError> {
+        let flags = self.names[ipn].flags_of_syllable(ipc);
+        let allowed = |key: &PhonemeKey| flags.allows_next(key);
+        let chain_weight = self.model.chain_weight();
+
+        // ordered by the indices for the reproducibility
+        let mut probabilities = BTreeMap::new();
+        let mut add = |list: &[usize], probability: f64| {
+            let weights = self.pooled_weights(
+                &list
+                    .iter()
+                    .map(|i| &self.outgoing_syllables[*i])
+                    .collect::<Vec<_>>(),
+            );
+            let total = weights.iter().sum::<f64>();
+            for (i, weight) in list.iter().zip(weights) {
+                *probabilities.entry(*i).or_insert(0.0) += probability * weight / total;
+            }
+        };
+        let incoming_phonemes = self.names[ipn].phonemes_of_syllable(ipc);
+        for (key, probability) in self.conn.distribution(incoming_phonemes, allowed) {
+            if let Some(list) = self.outgoing_tree.get(key) {
+                add(list, (1.0 - chain_weight) * probability);
+            }
+        }
+        if let Some(list) = self.successors((ipn, ipc)) {
+            add(list, chain_weight);
+        }
+
+        let candidates = probabilities
+            .into_iter()
+            .map(|(i, probability)| {
+                let syllable = &self.outgoing_syllables[i];
+                (syllable, probability * self.completion(progress, syllable))
+            })
+            .filter(|(_, probability)| *probability > 0.0)
+            .collect::<Vec<_>>();
+        let candidates = preferred(candidates, |(syllable, _)| {
+            self.prefers(options, count, syllable)
+        });
+        if candidates.is_empty() {
+            return Err(NameError::NoPhoneticConnection(incoming_phonemes.concat()));
+        }
+
+        let (list, mut weights): (Vec<_>, Vec<_>) = candidates.into_iter().unzip();
+        if options.reshapes() {
+            weights = self.reshape_syllables(&list, weights, options);
+        }
+        let total = weights.iter().sum::<f64>();
+        Ok(list
+            .into_iter()
+            .zip(weights)
+            .filter(|(_, weight)| *weight > 0.0)
+            .map(|(syllable, weight)| (syllable, weight / total))
+            .collect())
+    }
+
+    /// The first phonemes of the outgoing `syllable`, through which it is joined to the previous one.
+    pub(crate) fn join_key(&self, (ipn, ipc, _): &(usize, usize, ToRestore)) -> &PhonemeKey {
+        let key = self
+            .conn
+            .outgoing_key(self.names[*ipn].phonemes_of_syllable(*ipc));
+        self.outgoing_tree
+            .get_key_value(&key)
+            .map(|(key, _)| key)
+            .expect("every outgoing syllable is in the tree")
+    }
+}
@@ -0,0 +1,453 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::binary::{invalid, Decode, Encode};
+use crate::sorted_vec::{SortedVec, SortedVecBuilder};
+use crate::tokenizer::{Phoneme, PhonemeClass};
+use crate::NameError;
+
+/// The number of phonemes used as the context of the phonetic connection.
+///  `incoming` is the number of the last phonemes of the previous syllable,
+///  and `outgoing` is the number of the first phonemes of the next syllable.
+///
+/// Example: ContextOrder { incoming: 2, outgoing: 1 } connects `ŋk` in `(Berk /ˈbɜːŋk/)` to `s` in `(ston /stən/)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContextOrder {
+    pub incoming: usize,
+    pub outgoing: usize,
+}
+
+impl Default for ContextOrder {
+    fn default() -> Self {
+        Self {
+            incoming: 1,
+            outgoing: 1,
+        }
+    }
+}
+
+/// The smoothing of the phonetic connection, which gives probabilities to the connections not in the dataset.
+///  The smoothed connections only lead to the phonemes that start some outgoing syllable.
+///
+/// Example: NameGeneratorBuilder::new().smoothing(Smoothing::KneserNey { discount: 0.75 })
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Smoothing {
+    /// Only the connections in the dataset are used.
+    #[default]
+    None,
+    /// Add `alpha` to the count of every connection. `Additive(1.0)` is the Laplace smoothing.
+    Additive(f64),
+    /// Interpolate with the shorter context by the number of the distinct phonemes following the context,
+    ///  and finally with the frequency of the phonemes.
+    WittenBell,
+    /// Subtract `discount` from every count and give it to the shorter context,
+    ///  then to the class of the last phoneme like vowels or nasals, which shares the counts of its phonemes,
+    ///  and finally to the number of the distinct contexts each phoneme follows.
+    KneserNey { discount: f64 },
+}
+
+impl Smoothing {
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            Self::None | Self::WittenBell => true,
+            Self::Additive(alpha) => alpha.is_finite() && *alpha > 0.0,
+            Self::KneserNey { discount } => *discount > 0.0 && *discount <= 1.0,
+        }
+    }
+}
+
+/// The phonemes used as a key of the connection.
+pub(crate) type PhonemeKey = Vec<Phoneme>;
+
+/// The last `n` phonemes of `phonemes`.
+fn suffix(phonemes: &[Phoneme], n: usize) -> PhonemeKey {
+    phonemes[phonemes.len().saturating_sub(n)..].to_vec()
+}
+
+/// The first `n` phonemes of `phonemes`.
+fn prefix(phonemes: &[Phoneme], n: usize) -> PhonemeKey {
+    phonemes[..n.min(phonemes.len())].to_vec()
+}
+
+pub(crate) struct PhoneticConnectionBuilder {
+    order: ContextOrder,
+    conn: HashMap<PhonemeKey, HashMap<PhonemeKey, f64>>,
+}
+
+impl PhoneticConnectionBuilder {
+    pub(crate) fn new(order: ContextOrder) -> PhoneticConnectionBuilder {
+        PhoneticConnectionBuilder {
+            order,
+            conn: HashMap::new(),
+        }
+    }
+
+    /// The key of the outgoing syllable `phonemes`, which is its first phonemes.
+    pub(crate) fn outgoing_key(&self, phonemes: &[Phoneme]) -> PhonemeKey {
+        prefix(phonemes, self.order.outgoing)
+    }
+
+    /// Count the connection between the previous syllable `incoming` and the next syllable `outgoing` by `weight`
+    /// for every length of the incoming context up to the order.
+    pub(crate) fn add_pair(&mut self, incoming: &[Phoneme], outgoing: &[Phoneme], weight: f64) {
+        let outgoing_key = self.outgoing_key(outgoing);
+        for n in 1..=self.order.incoming.min(incoming.len()) {
+            self.conn
+                .entry(suffix(incoming, n))
+                .or_default()
+                .entry(outgoing_key.clone())
+                .and_modify(|e| *e += weight)
+                .or_insert(weight);
+        }
+    }
+
+    /// Build the connection, smoothed over `targets`, which are the keys of all the outgoing syllables.
+    pub(crate) fn build(self, smoothing: Smoothing, targets: &[PhonemeKey]) -> PhoneticConnection {
+        if smoothing != Smoothing::None {
+            return self.build_smoothed(smoothing, targets);
+        }
+        let mut builder = SortedVecBuilder::new();
+        self.conn.iter().for_each(|(k, v)| {
+            // summed in the order of the keys to build the same probabilities for the same names
+            let mut v = v.iter().collect::<Vec<(&PhonemeKey, &f64)>>();
+            v.sort_by(|a, b| a.0.cmp(b.0));
+
+            let mut sum = 0.0;
+            for (_, v2) in &v {
+                sum += *v2;
+            }
+
+            let mut set = SortedVecBuilder::new();
+            let mut prop = 0.0;
+
+            for (k2, v2) in v {
+                prop += *v2 / sum;
+                set.push(prop, k2.clone());
+            }
+
+            let set = set.build();
+            builder.push(k.clone(), set);
+        });
+
+        PhoneticConnection {
+            order: self.order,
+            conn: builder.build(),
+        }
+    }
+
+    fn build_smoothed(self, smoothing: Smoothing, targets: &[PhonemeKey]) -> PhoneticConnection {
+        let mut targets = targets.to_vec();
+        targets.sort();
+        targets.dedup();
+        let index = targets
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key, i))
+            .collect::<HashMap<_, _>>();
+        let uniform = vec![1.0 / targets.len() as f64; targets.len()];
+        let dense = |counts: &HashMap<PhonemeKey, f64>| {
+            let mut dense = vec![0.0; targets.len()];
+            counts
+                .iter()
+                .for_each(|(key, count)| dense[index[key]] = *count);
+            dense
+        };
+
+        // the shortest contexts, which are counted by the distinct contexts or the frequency of the phonemes
+        let mut unigram = vec![0.0; targets.len()];
+        let mut sorted_contexts = self.conn.keys().collect::<Vec<_>>();
+        sorted_contexts.sort_by(|a, b| a.len().cmp(&b.len()).then(a.cmp(b)));
+        for context in sorted_contexts.iter().filter(|c| c.len() == 1) {
+            for (u, count) in unigram.iter_mut().zip(dense(&self.conn[*context])) {
+                *u += match smoothing {
+                    Smoothing::KneserNey { .. } if count > 0.0 => 1.0,
+                    Smoothing::KneserNey { .. } => 0.0,
+                    _ => count,
+                };
+            }
+        }
+        let base = match smoothing {
+            Smoothing::WittenBell => interpolate_witten_bell(&unigram, &uniform),
+            Smoothing::KneserNey { discount } => {
+                interpolate_kneser_ney(&unigram, &uniform, discount)
+            }
+            _ => uniform.clone(),
+        };
+        // the single phonemes back off to their classes, which pool the counts of their phonemes
+        let mut classes: HashMap<PhonemeClass, Vec<f64>> = HashMap::new();
+        if let Smoothing::KneserNey { discount } = smoothing {
+            for context in sorted_contexts.iter().filter(|c| c.len() == 1) {
+                let counts = classes
+                    .entry(PhonemeClass::of(&context[0]))
+                    .or_insert_with(|| vec![0.0; targets.len()]);
+                for (c, count) in counts.iter_mut().zip(dense(&self.conn[*context])) {
+                    *c += count;
+                }
+            }
+            classes
+                .values_mut()
+                .for_each(|counts| *counts = interpolate_kneser_ney(counts, &base, discount));
+        }
+
+        let mut distributions: HashMap<PhonemeKey, Vec<f64>> = HashMap::new();
+        for context in sorted_contexts {
+            let lower = match context.len() {
+                1 => classes.get(&PhonemeClass::of(&context[0])).unwrap_or(&base),
+                n => &distributions[&suffix(context, n - 1)],
+            };
+            let counts = dense(&self.conn[context]);
+            let distribution = match smoothing {
+                Smoothing::Additive(alpha) => {
+                    let total = counts.iter().sum::<f64>() + alpha * targets.len() as f64;
+                    counts.iter().map(|c| (c + alpha) / total).collect()
+                }
+                Smoothing::WittenBell => interpolate_witten_bell(&counts, lower),
+                Smoothing::KneserNey { discount } => {
+                    interpolate_kneser_ney(&counts, lower, discount)
+                }
+                Smoothing::None => counts,
+            };
+            distributions.insert(context.clone(), distribution);
+        }
+
+        let mut builder = SortedVecBuilder::new();
+        let mut distributions = distributions.into_iter().collect::<Vec<_>>();
+        distributions.sort_by(|a, b| a.0.cmp(&b.0));
+        for (context, distribution) in distributions {
+            let sum = distribution.iter().sum::<f64>();
+            let mut set = SortedVecBuilder::new();
+            let mut prop = 0.0;
+            for (key, p) in targets.iter().zip(distribution) {
+                if p > 0.0 {
+                    prop += p / sum;
+                    set.push(prop, key.clone());
+                }
+            }
+            builder.push(context, set.build());
+        }
+
+        PhoneticConnection {
+            order: self.order,
+            conn: builder.build(),
+        }
+    }
+}
+
+/// Extract the key of `candidates` at the cumulative probability `prop`. `candidates` must not be empty.
+///  The probability of the extracted key is returned together.
+pub(crate) fn extract_forward<'a>(
+    candidates: &[(&'a PhonemeKey, f64)],
+    prop: f64,
+) -> (&'a PhonemeKey, f64) {
+    let mut cumulative = 0.0;
+    *candidates
+        .iter()
+        .find(|(_, p)| {
+            cumulative += p;
+            cumulative >= prop
+        })
+        .unwrap_or(&candidates[candidates.len() - 1])
+}
+
+/// Extract the key of `candidates` of the backward connection at the cumulative probability `prop`. `candidates` must not be empty.
+///  The keys of the backward connection are the reversed last phonemes of the previous syllable,
+///  so the key is returned in the order of the name, with its probability.
+pub(crate) fn extract_backward(candidates: &[(&PhonemeKey, f64)], prop: f64) -> (PhonemeKey, f64) {
+    let (key, probability) = extract_forward(candidates, prop);
+    (reversed(key), probability)
+}
+
+/// The phonemes in the reversed order, on which the backward connection runs.
+pub(crate) fn reversed(phonemes: &[Phoneme]) -> PhonemeKey {
+    phonemes.iter().rev().cloned().collect()
+}
+
+/// Witten-Bell interpolation of `counts` with the distribution `lower`.
+fn interpolate_witten_bell(counts: &[f64], lower: &[f64]) -> Vec<f64> {
+    let total = counts.iter().sum::<f64>();
+    let types = counts.iter().filter(|c| **c > 0.0).count() as f64;
+    counts
+        .iter()
+        .zip(lower)
+        .map(|(c, l)| (c + types * l) / (total + types))
+        .collect()
+}
+
+/// Absolute discounting of `counts` by `discount`, interpolated with the distribution `lower`.
+fn interpolate_kneser_ney(counts: &[f64], lower: &[f64], discount: f64) -> Vec<f64> {
+    let total = counts.iter().sum::<f64>();
+    let types = counts.iter().filter(|c| **c > 0.0).count() as f64;
+    counts
+        .iter()
+        .zip(lower)
+        .map(|(c, l)| ((c - discount).max(0.0) + discount * types * l) / total)
+        .collect()
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub(crate) struct PhoneticConnection {
+    order: ContextOrder,
+    conn: SortedVec<PhonemeKey, SortedVec<f64, PhonemeKey>>,
+}
+
+impl PhoneticConnection {
+    pub(crate) fn order(&self) -> ContextOrder {
+        self.order
+    }
+
+    /// The key of the outgoing syllable `phonemes`, which is its first phonemes.
+    pub(crate) fn outgoing_key(&self, phonemes: &[Phoneme]) -> PhonemeKey {
+        prefix(phonemes, self.order.outgoing)
+    }
+
+    /// The context of the previous syllable `phonemes`, which determines its distribution.
+    pub(crate) fn context_key(&self, phonemes: &[Phoneme]) -> PhonemeKey {
+        suffix(phonemes, self.order.incoming)
+    }
+
+    /// The distribution for the previous syllable `phonemes`.
+    ///  The longest context that has data is used, backing off to the shorter ones.
+    fn context(&self, phonemes: &[Phoneme]) -> Option<&SortedVec<f64, PhonemeKey>> {
+        (1..=self.order.incoming.min(phonemes.len()))
+            .rev()
+            .find_map(|n| self.conn.get(&suffix(phonemes, n)))
+    }
+
+    /// The keys satisfying `filter` that can follow the previous syllable `phonemes`, with the probabilities renormalized.
+    pub(crate) fn distribution(
+        &self,
+        phonemes: &[Phoneme],
+        filter: impl Fn(&PhonemeKey) -> bool,
+    ) -> Vec<(&PhonemeKey, f64)> {
+        let Some(set) = self.context(phonemes) else {
+            return vec![];
+        };
+        let mut candidates = vec![];
+        let mut previous = 0.0;
+        let mut sum = 0.0;
+        for (cumulative, key) in set.iter() {
+            if filter(key) {
+                sum += cumulative - previous;
+                candidates.push((key, cumulative - previous));
+            }
+            previous = *cumulative;
+        }
+        candidates.iter_mut().for_each(|(_, p)| *p /= sum);
+        candidates
+    }
+
+    /// The probability of `key` following the previous syllable `phonemes`, among the keys satisfying `filter`.
+    pub(crate) fn probability(
+        &self,
+        phonemes: &[Phoneme],
+        key: &PhonemeKey,
+        filter: impl Fn(&PhonemeKey) -> bool,
+    ) -> f64 {
+        self.distribution(phonemes, filter)
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map_or(0.0, |(_, p)| *p)
+    }
+
+    pub(crate) fn has_incoming(&self, phonemes: &[Phoneme]) -> bool {
+        self.context(phonemes).is_some()
+    }
+
+    pub(crate) fn outgoing_keys(&self) -> impl Iterator<Item = &PhonemeKey> + '_ {
+        self.conn
+            .iter()
+            .flat_map(|(_, set)| set.iter().map(|(_, key)| key))
+    }
+}
+
+impl Encode for PhoneticConnection {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.order.incoming.encode(writer)?;
+        self.order.outgoing.encode(writer)?;
+        self.conn.encode(writer)
+    }
+}
+
+impl Encode for Smoothing {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        match self {
+            Self::None => 0usize.encode(writer),
+            Self::Additive(alpha) => {
+                1usize.encode(writer)?;
+                alpha.encode(writer)
+            }
+            Self::WittenBell => 2usize.encode(writer),
+            Self::KneserNey { discount } => {
+                3usize.encode(writer)?;
+                discount.encode(writer)
+            }
+        }
+    }
+}
+
+impl Decode for Smoothing {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        let smoothing = match usize::decode(reader)? {
+            0 => Self::None,
+            1 => Self::Additive(f64::decode(reader)?),
+            2 => Self::WittenBell,
+            3 => Self::KneserNey {
+                discount: f64::decode(reader)?,
+            },
+            _ => return Err(invalid("unknown smoothing")),
+        };
+        if !smoothing.is_valid() {
+            return Err(invalid("invalid smoothing"));
+        }
+        Ok(smoothing)
+    }
+}
+
+impl Decode for PhoneticConnection {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Ok(Self {
+            order: ContextOrder {
+                incoming: usize::decode(reader)?,
+                outgoing: usize::decode(reader)?,
+            },
+            conn: SortedVec::decode(reader)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContextOrder, PhonemeKey, PhoneticConnectionBuilder, Smoothing};
+
+    fn key(phoneme: &str) -> PhonemeKey {
+        vec![phoneme.to_string()]
+    }
+
+    #[test]
+    fn kneser_ney_backs_off_to_phoneme_classes() {
+        let mut builder = PhoneticConnectionBuilder::new(ContextOrder::default());
+        // `t` is followed by `f`, while `d` and `m` are followed only by `l`
+        builder.add_pair(&key("t"), &key("f"), 1.0);
+        builder.add_pair(&key("d"), &key("l"), 1.0);
+        builder.add_pair(&key("m"), &key("l"), 1.0);
+        let conn = builder.build(
+            Smoothing::KneserNey { discount: 0.5 },
+            &[key("f"), key("l")],
+        );
+        // `d` is a plosive like `t`, so it leads to `f` more often than the nasal `m`
+        let plosive = conn.probability(&key("d"), &key("f"), |_| true);
+        let nasal = conn.probability(&key("m"), &key("f"), |_| true);
+        assert!(plosive > nasal, "{plosive} <= {nasal}");
+    }
+}
@@ -0,0 +1,193 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::io::BufRead;
+
+use crate::tokenizer::{CharTokenizer, Tokenizer, STRESS_MARKER};
+use crate::{Name, NameError, NameGeneratorBuilder, SyllableFlags};
+
+/// The separator between the syllables in the last column.
+const SYLLABLE_SEPARATOR: char = ':';
+/// The separator between the letter and the phonics of a syllable.
+const PHONICS_SEPARATOR: char = '_';
+/// The syllable `+_+` separates words.
+const WORD_SEPARATOR: &str = "+";
+/// The separator between the tags in the tag column.
+const TAG_SEPARATOR: char = ';';
+/// The marker at the beginning (end) of the phonics of a word-initial (word-final) syllable.
+const BOUNDARY_MARKER: char = '*';
+
+/// The reader for datasets of separated names.
+///  Each line is a comma separated record whose last column lists the syllables as `letter_phonics:letter_phonics`.
+///  The other columns are ignored unless they are set as the weight column or the tag column, and empty lines are skipped.
+///
+///  The markers of the examples are converted into `SyllableFlags` and removed from the syllables.
+///  - The syllable `+_+` is a word separator, rendered as a space.
+///  - `*` at the beginning (end) of the phonics flags the syllable as word-initial (word-final).
+///  - The syllable whose phonics contains `ˈ` is flagged as stressed.
+///
+/// Example: `Bedford,ˈbɛdfərd,Bed_ˈbɛd:ford_fərd` -> Name::new(vec![("Bed", "ˈbɛd"), ("ford", "fərd")])
+pub struct DatasetReader<R> {
+    reader: R,
+    tokenizer: Box<dyn Tokenizer>,
+    weight_column: Option<usize>,
+    tag_column: Option<usize>,
+}
+
+impl<R: BufRead> DatasetReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            tokenizer: Box::new(CharTokenizer),
+            weight_column: None,
+            tag_column: None,
+        }
+    }
+
+    /// Set the tokenizer to split the phonics into phonemes. `CharTokenizer` is used by default.
+    pub fn tokenizer(mut self, tokenizer: impl Tokenizer + 'static) -> Self {
+        self.tokenizer = Box::new(tokenizer);
+        self
+    }
+
+    /// Read the weight of each name from the `column`-th column (0-based), which must be a positive number.
+    ///  Every name has the weight 1 by default.
+    ///
+    /// Example: `London,8961989,ˈlʌndən,Lon_ˈlʌn:don_dən` with `weight_column(1)`
+    pub fn weight_column(mut self, column: usize) -> Self {
+        self.weight_column = Some(column);
+        self
+    }
+
+    /// Read the tags of each name from the `column`-th column (0-based), separated by `;`.
+    ///  The names have no tag by default.
+    ///
+    /// Example: `Thames,river;london,ˈtɛmz,Tha_ˈtɛ:mes_mz` with `tag_column(1)`
+    pub fn tag_column(mut self, column: usize) -> Self {
+        self.tag_column = Some(column);
+        self
+    }
+
+    /// Read all the names in the dataset.
+    pub fn read_names(self) -> Result<Vec<Name>, NameError> {
+        Ok(self
+            .read_weighted_names()?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect())
+    }
+
+    /// Read all the names in the dataset with their weights.
+    pub fn read_weighted_names(self) -> Result<Vec<(Name, f64)>, NameError> {
+        let mut names = vec![];
+        for (i, line) in self.reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let weight = match self.weight_column {
+                Some(column) => parse_weight(&line, i + 1, column)?,
+                None => 1.0,
+            };
+            let mut name = parse_line(&line, i + 1, self.tokenizer.as_ref())?;
+            if let Some(column) = self.tag_column {
+                name = name.with_tags(&parse_tags(&line, column));
+            }
+            names.push((name, weight));
+        }
+        Ok(names)
+    }
+
+    /// Read all the names in the dataset and add them to `builder` with their weights.
+    pub fn add_to_builder(
+        self,
+        builder: NameGeneratorBuilder,
+    ) -> Result<NameGeneratorBuilder, NameError> {
+        Ok(builder.bulk_add_weighted_names(self.read_weighted_names()?))
+    }
+}
+
+/// Parse the last column of `line`. `line_number` is only used for error reporting.
+fn parse_line(
+    line: &str,
+    line_number: usize,
+    tokenizer: &dyn Tokenizer,
+) -> Result<Name, NameError> {
+    let line = line.trim_end();
+    let mut offset = line.rfind(',').map(|i| i + 1).unwrap_or(0);
+
+    let mut syllables = vec![];
+    for entry in line[offset..].split(SYLLABLE_SEPARATOR) {
+        let column = column_of(line, offset);
+        let (letter, phonics) = match entry.split_once(PHONICS_SEPARATOR) {
+            Some((letter, phonics)) if !phonics.contains(PHONICS_SEPARATOR) => (letter, phonics),
+            _ => {
+                return Err(NameError::MalformedSyllable {
+                    line: line_number,
+                    column,
+                    entry: entry.to_string(),
+                })
+            }
+        };
+        if letter == WORD_SEPARATOR && phonics == WORD_SEPARATOR {
+            syllables.push((" ".to_string(), " ".to_string(), SyllableFlags::separator()));
+            offset += entry.len() + 1;
+            continue;
+        }
+
+        let flags = SyllableFlags {
+            word_initial: phonics.starts_with(BOUNDARY_MARKER),
+            word_final: phonics.ends_with(BOUNDARY_MARKER),
+            separator: false,
+            stressed: phonics.contains(STRESS_MARKER),
+        };
+        let phonics = phonics.trim_matches(BOUNDARY_MARKER);
+        if phonics.is_empty() {
+            return Err(NameError::EmptyPhonics {
+                line: line_number,
+                column: column_of(line, offset + letter.len() + 1),
+            });
+        }
+        syllables.push((letter.to_string(), phonics.to_string(), flags));
+        offset += entry.len() + 1;
+    }
+
+    Name::from_syllables(syllables, tokenizer)
+}
+
+/// Parse the `column`-th column of `line` as a weight. `line_number` is only used for error reporting.
+fn parse_weight(line: &str, line_number: usize, column: usize) -> Result<f64, NameError> {
+    let mut offset = 0;
+    let mut fields = line.split(',');
+    for _ in 0..column {
+        offset += fields.next().map_or(0, |field| field.len() + 1);
+    }
+    let entry = fields.next().unwrap_or_default().trim();
+    match entry.parse::<f64>() {
+        Ok(weight) if weight.is_finite() && weight > 0.0 => Ok(weight),
+        _ => Err(NameError::MalformedWeight {
+            line: line_number,
+            column: column_of(line, offset.min(line.len())),
+            entry: entry.to_string(),
+        }),
+    }
+}
+
+/// Parse the `column`-th column of `line` as the tags, skipping the empty ones.
+fn parse_tags(line: &str, column: usize) -> Vec<&str> {
+    line.split(',')
+        .nth(column)
+        .unwrap_or_default()
+        .split(TAG_SEPARATOR)
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// The 1-based column of the character at `byte_offset`.
+fn column_of(line: &str, byte_offset: usize) -> usize {
+    line[..byte_offset].chars().count() + 1
+}
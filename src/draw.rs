@@ -0,0 +1,67 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::NameError;
+
+/// The source of the random draws in the generation.
+pub(crate) trait Draw {
+    /// Draw a value in `[0, 1)`.
+    fn next_f64(&mut self) -> Result<f64, NameError>;
+
+    /// Draw an index in `0..len`. `len` must be positive.
+    fn next_index(&mut self, len: usize) -> Result<usize, NameError>;
+
+    /// Draw an index of `weights` with the probability proportional to the weight.
+    ///  The uniform weights are drawn by `next_index`. `weights` must not be empty.
+    fn next_weighted(&mut self, weights: &[f64]) -> Result<usize, NameError> {
+        if weights.iter().all(|w| *w == weights[0]) {
+            return self.next_index(weights.len());
+        }
+        let p = self.next_f64()? * weights.iter().sum::<f64>();
+        let mut cumulative = 0.0;
+        Ok(weights
+            .iter()
+            .position(|w| {
+                cumulative += w;
+                cumulative > p
+            })
+            .unwrap_or(weights.len() - 1))
+    }
+}
+
+/// The draws from a closure returning values in `[0, 1)`.
+pub(crate) struct FnDraw<F>(pub(crate) F);
+
+impl<F: FnMut() -> f64> Draw for FnDraw<F> {
+    fn next_f64(&mut self) -> Result<f64, NameError> {
+        let p = (self.0)();
+        if (0.0..1.0).contains(&p) {
+            Ok(p)
+        } else {
+            Err(NameError::InvalidRandomValue(p))
+        }
+    }
+
+    fn next_index(&mut self, len: usize) -> Result<usize, NameError> {
+        Ok((self.next_f64()? * len as f64) as usize)
+    }
+}
+
+/// The draws from a random number generator of `rand`.
+///  The indices are drawn as integers, so they are not biased by the truncation of floats.
+#[cfg(feature = "rand")]
+pub(crate) struct RngDraw<'a, R: ?Sized>(pub(crate) &'a mut R);
+
+#[cfg(feature = "rand")]
+impl<R: rand::Rng + ?Sized> Draw for RngDraw<'_, R> {
+    fn next_f64(&mut self) -> Result<f64, NameError> {
+        Ok(self.0.gen::<f64>())
+    }
+
+    fn next_index(&mut self, len: usize) -> Result<usize, NameError> {
+        Ok(self.0.gen_range(0..len))
+    }
+}
@@ -0,0 +1,169 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! The evaluation of the generated names, to pick better ones.
+//!
+//! A `NameEvaluator` scores a generated name, or rejects it by returning `None`.
+//! The scores of the built-in evaluators are in log-scale, so adding them up corresponds to multiplying the likelihoods.
+//!
+//! Example: Veto::new(WeightedSum::new().add(1.0, SyllableCount { mean: 2.25, std_dev: 0.8 }))
+//!     .veto(RejectSource).veto(RejectRepeatedSyllables)
+
+use crate::GeneratedName;
+
+/// The evaluator of the generated names. The higher score is the better.
+pub trait NameEvaluator {
+    /// Score `name`, or return `None` to reject it.
+    fn evaluate(&self, name: &GeneratedName) -> Option<f64>;
+}
+
+impl<F: Fn(&GeneratedName) -> Option<f64>> NameEvaluator for F {
+    fn evaluate(&self, name: &GeneratedName) -> Option<f64> {
+        self(name)
+    }
+}
+
+/// The logarithm of the density of the normal distribution.
+fn log_normal_density(x: f64, mean: f64, std_dev: f64) -> f64 {
+    -(x - mean).powi(2) / (2.0 * std_dev.powi(2))
+        - (std_dev * (2.0 * std::f64::consts::PI).sqrt()).ln()
+}
+
+/// The number of syllables of each word, split by the separators.
+fn word_lengths(name: &GeneratedName) -> Vec<usize> {
+    name.syllable_info().iter().fold(vec![0], |mut acc, info| {
+        if name.is_separator(info) {
+            acc.push(0);
+        } else {
+            *acc.last_mut().unwrap() += 1;
+        }
+        acc
+    })
+}
+
+/// Reject the names identical to a name in the dataset which they are generated from.
+///  Scores 0 otherwise.
+pub struct RejectSource;
+
+impl NameEvaluator for RejectSource {
+    fn evaluate(&self, name: &GeneratedName) -> Option<f64> {
+        let content = name.content();
+        if name
+            .sources()
+            .iter()
+            .any(|source| source.content() == content)
+        {
+            None
+        } else {
+            Some(0.0)
+        }
+    }
+}
+
+/// Reject the names with the same letters in adjacent syllables ignoring the case, like `Woodwood`.
+///  Scores 0 otherwise.
+pub struct RejectRepeatedSyllables;
+
+impl NameEvaluator for RejectRepeatedSyllables {
+    fn evaluate(&self, name: &GeneratedName) -> Option<f64> {
+        if name
+            .syllables()
+            .windows(2)
+            .any(|pair| pair[0].0.to_uppercase() == pair[1].0.to_uppercase())
+        {
+            None
+        } else {
+            Some(0.0)
+        }
+    }
+}
+
+/// Score the number of syllables of the longest word by the log-density of the normal distribution.
+pub struct SyllableCount {
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+impl NameEvaluator for SyllableCount {
+    fn evaluate(&self, name: &GeneratedName) -> Option<f64> {
+        let max_length = word_lengths(name).into_iter().max()?;
+        Some(log_normal_density(
+            max_length as f64,
+            self.mean,
+            self.std_dev,
+        ))
+    }
+}
+
+/// Score the number of words by the log-density of the normal distribution.
+pub struct WordCount {
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+impl NameEvaluator for WordCount {
+    fn evaluate(&self, name: &GeneratedName) -> Option<f64> {
+        let count = word_lengths(name).len();
+        Some(log_normal_density(count as f64, self.mean, self.std_dev))
+    }
+}
+
+/// The weighted sum of the scores of the evaluators.
+///  The name rejected by any of the evaluators is rejected.
+#[derive(Default)]
+pub struct WeightedSum {
+    evaluators: Vec<(f64, Box<dyn NameEvaluator>)>,
+}
+
+impl WeightedSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(mut self, weight: f64, evaluator: impl NameEvaluator + 'static) -> Self {
+        self.evaluators.push((weight, Box::new(evaluator)));
+        self
+    }
+}
+
+impl NameEvaluator for WeightedSum {
+    fn evaluate(&self, name: &GeneratedName) -> Option<f64> {
+        self.evaluators
+            .iter()
+            .map(|(weight, evaluator)| Some(weight * evaluator.evaluate(name)?))
+            .sum()
+    }
+}
+
+/// The score of an evaluator, for the names not rejected by any of the vetoes.
+///  The scores of the vetoes are ignored.
+pub struct Veto {
+    evaluator: Box<dyn NameEvaluator>,
+    vetoes: Vec<Box<dyn NameEvaluator>>,
+}
+
+impl Veto {
+    pub fn new(evaluator: impl NameEvaluator + 'static) -> Self {
+        Self {
+            evaluator: Box::new(evaluator),
+            vetoes: vec![],
+        }
+    }
+
+    pub fn veto(mut self, veto: impl NameEvaluator + 'static) -> Self {
+        self.vetoes.push(Box::new(veto));
+        self
+    }
+}
+
+impl NameEvaluator for Veto {
+    fn evaluate(&self, name: &GeneratedName) -> Option<f64> {
+        for veto in &self.vetoes {
+            veto.evaluate(name)?;
+        }
+        self.evaluator.evaluate(name)
+    }
+}
@@ -0,0 +1,116 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::tokenizer::Phoneme;
+use crate::{Letter, Name, Phonics, SyllableFlags, SyllableInfo};
+
+/// The name generated by `NameGenerator`, which refers to the names in the dataset its syllables come from.
+///
+/// Example: Berkles -> syllables: [("Berk", "ˈbɜːrk"), ("les", "əlz")], joins: [None, Some(["ə"])]
+#[derive(Debug, Clone)]
+pub struct GeneratedName<'a> {
+    names: &'a [Name],
+    syllable_info: Vec<SyllableInfo>,
+}
+
+impl<'a> GeneratedName<'a> {
+    pub(crate) fn new(names: &'a [Name], syllable_info: Vec<SyllableInfo>) -> Self {
+        Self {
+            names,
+            syllable_info,
+        }
+    }
+
+    pub(crate) fn is_separator(&self, info: &SyllableInfo) -> bool {
+        self.names[info.name_index]
+            .flags_of_syllable(info.syllable_index)
+            .separator
+    }
+
+    fn syllable(&self, info: &SyllableInfo) -> &'a (Letter, Phonics) {
+        &self.names[info.name_index].syllables()[info.syllable_index]
+    }
+
+    pub fn content(&self) -> Letter {
+        self.syllable_info
+            .iter()
+            .map(|info| self.syllable(info).0.as_str())
+            .collect()
+    }
+
+    pub fn script(&self) -> Phonics {
+        self.syllable_info
+            .iter()
+            .map(|info| self.syllable(info).1.as_str())
+            .collect()
+    }
+
+    /// Get the letter and the phonics of each syllable
+    pub fn syllables(&self) -> Vec<&'a (Letter, Phonics)> {
+        self.syllable_info
+            .iter()
+            .map(|info| self.syllable(info))
+            .collect()
+    }
+
+    /// Get the name in the dataset which each syllable comes from
+    pub fn sources(&self) -> Vec<&'a Name> {
+        self.syllable_info
+            .iter()
+            .map(|info| &self.names[info.name_index])
+            .collect()
+    }
+
+    /// Get the phonemes through which each syllable is connected to the previous one.
+    ///  `None` for the syllables chosen without the phonetic connection, like the first one.
+    pub fn joins(&self) -> Vec<Option<&[Phoneme]>> {
+        self.syllable_info
+            .iter()
+            .map(|info| info.join.as_deref())
+            .collect()
+    }
+
+    /// Get the detailed information of the syllables
+    pub fn syllable_info(&self) -> &[SyllableInfo] {
+        &self.syllable_info
+    }
+
+    /// The log-probability with which the name was generated along its path.
+    pub fn log_probability(&self) -> f64 {
+        self.syllable_info
+            .iter()
+            .map(|info| info.log_probability)
+            .sum()
+    }
+
+    /// Convert into a `Name`, with the phonemes and the flags of the syllables in the dataset.
+    ///  The result can be added to a `NameGeneratorBuilder` or scored by `NameGenerator::score`.
+    pub fn to_name(&self) -> Name {
+        let len = self.syllable_info.len();
+        let separator = |i: usize| self.is_separator(&self.syllable_info[i]);
+        let mut syllables = vec![];
+        let mut phonemes = vec![];
+        let mut flags = vec![];
+        for (i, info) in self.syllable_info.iter().enumerate() {
+            let source = &self.names[info.name_index];
+            let mut syllable_flags: SyllableFlags = source.flags_of_syllable(info.syllable_index);
+            if !syllable_flags.separator {
+                syllable_flags.word_initial |= i == 0 || separator(i - 1);
+                syllable_flags.word_final |= i + 1 == len || separator(i + 1);
+            }
+            syllables.push(self.syllable(info).clone());
+            phonemes.push(source.phonemes_of_syllable(info.syllable_index).to_vec());
+            flags.push(syllable_flags);
+        }
+        Name::from_parts(syllables, phonemes, flags)
+    }
+}
+
+impl From<GeneratedName<'_>> for Name {
+    fn from(name: GeneratedName<'_>) -> Self {
+        name.to_name()
+    }
+}
@@ -0,0 +1,115 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashSet;
+
+use crate::draw::FnDraw;
+use crate::{
+    GeneratedName, GenerationOptions, Letter, NameError, NameGenerator, Phonics, PreparedOptions,
+};
+
+/// The default value of `Names::max_attempts`.
+pub const DEFAULT_MAX_ATTEMPTS: usize = 1000;
+
+/// The infinite iterator of the generated names, created by `NameGenerator::iter`.
+///  A name is identified by its letter and phonics together.
+///
+/// Example: generator.iter(|| rng.gen()).unique().novel().take_unique(100)
+pub struct Names<'a, F> {
+    generator: &'a NameGenerator,
+    rand_fn: F,
+    options: GenerationOptions,
+    // names already emitted, if they are skipped
+    emitted: Option<HashSet<(Letter, Phonics)>>,
+    // names in the dataset, if they are skipped
+    dataset: Option<HashSet<(Letter, Phonics)>>,
+    max_attempts: usize,
+    // `options` prepared by the first generation
+    prepared: Option<PreparedOptions<'a>>,
+}
+
+impl<'a, F: FnMut() -> f64> Names<'a, F> {
+    pub(crate) fn new(generator: &'a NameGenerator, rand_fn: F) -> Self {
+        Self {
+            generator,
+            rand_fn,
+            options: GenerationOptions::default(),
+            emitted: None,
+            dataset: None,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            prepared: None,
+        }
+    }
+
+    /// Set the options for the generation.
+    pub fn options(mut self, options: GenerationOptions) -> Self {
+        self.options = options;
+        self.prepared = None;
+        self
+    }
+
+    /// Never yield a name already yielded.
+    pub fn unique(mut self) -> Self {
+        self.emitted.get_or_insert_with(HashSet::new);
+        self
+    }
+
+    /// Never yield a name present in the dataset.
+    pub fn novel(mut self) -> Self {
+        self.dataset = Some(
+            self.generator
+                .names()
+                .iter()
+                .map(|name| (name.content(), name.script()))
+                .collect(),
+        );
+        self
+    }
+
+    /// The number of the generated names skipped in a row before giving up.
+    ///  The iterator yields `NameError::AttemptsExhausted` once it is reached.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Collect `n` distinct names.
+    ///  An error is returned if the model can not produce enough distinct names within the attempts.
+    pub fn take_unique(self, n: usize) -> Result<Vec<GeneratedName<'a>>, NameError> {
+        self.unique().take(n).collect()
+    }
+}
+
+impl<'a, F: FnMut() -> f64> Iterator for Names<'a, F> {
+    type Item = Result<GeneratedName<'a>, NameError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.prepared.is_none() {
+            match self.generator.prepare(&self.options) {
+                Ok(prepared) => self.prepared = Some(prepared),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let prepared = self.prepared.as_ref()?;
+        for _ in 0..self.max_attempts {
+            let name = match prepared.generate_with_draw(&mut FnDraw(&mut self.rand_fn)) {
+                Ok(name) => name,
+                Err(e) => return Some(Err(e)),
+            };
+            let key = (name.content(), name.script());
+            if self.dataset.as_ref().is_some_and(|set| set.contains(&key)) {
+                continue;
+            }
+            if let Some(emitted) = &mut self.emitted {
+                if !emitted.insert(key) {
+                    continue;
+                }
+            }
+            return Some(Ok(name));
+        }
+        Some(Err(NameError::AttemptsExhausted(self.max_attempts)))
+    }
+}
@@ -5,287 +5,1189 @@
  */
 
 use std::collections::HashMap;
+use std::io::{Read, Write};
 use thiserror::Error;
 
-use sorted_vec::{SortedVec, SortedVecBuilder};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use binary::{Decode, Encode};
+use draw::{Draw, FnDraw};
+use evaluate::NameEvaluator;
+
+use connection::{reversed, PhonemeKey, PhoneticConnection, PhoneticConnectionBuilder};
+use constraint::{advance, MatchState, Progress, Steering};
+use options::preferred;
+use tokenizer::Phoneme;
 
 /// The letter of the syllable
-type Letter = String;
+pub type Letter = String;
 /// The phonetic representation of the letter
-type Phonics = String;
+pub type Phonics = String;
 /// The parameter of syllables that can be the next syllable or not
 type ToRestore = bool;
+/// The syllable chosen in a step of the generation, with its probability and the phonemes of the join
+type Step<'a> = (&'a (usize, usize, ToRestore), f64, Option<&'a PhonemeKey>);
+/// The syllable that can be chosen, with its probability
+type Candidate<'a> = (&'a (usize, usize, ToRestore), f64);
 
+mod backward;
+mod binary;
+mod chain;
+mod connection;
+mod constraint;
+pub mod dataset;
+mod draw;
+pub mod evaluate;
+mod generated;
+mod iter;
+mod mix;
+mod name;
+mod options;
+mod position;
+mod prepared;
+pub mod random;
+mod search;
+#[cfg(feature = "serde")]
+mod serde_format;
 mod sorted_vec;
+mod stop;
+mod tag;
+pub mod tokenizer;
+
+pub use binary::FORMAT_VERSION;
+pub use chain::Model;
+pub use connection::{ContextOrder, Smoothing};
+pub use generated::GeneratedName;
+pub use iter::{Names, DEFAULT_MAX_ATTEMPTS};
+pub use name::{Name, SyllableFlags};
+pub use options::{GenerationOptions, DEFAULT_SYLLABLE_LIMIT};
+pub use position::{Position, PositionFrequency};
+pub use prepared::PreparedOptions;
+pub use stop::{StopModel, Termination};
+pub use tag::DEFAULT_TAG_BACKOFF;
 
 #[derive(Error, Debug)]
 pub enum NameError {
     #[error("empty string detected")]
     EmptyString,
+    #[error("a name must have at least one syllable")]
+    EmptyName,
+    #[error("no phoneme found in `{0}`")]
+    NoPhoneme(String),
+    #[error("a separator must be between two syllables")]
+    MisplacedSeparator,
+    #[error(
+        "malformed syllable `{entry}` at line {line}, column {column}: expected `letter_phonics`"
+    )]
+    MalformedSyllable {
+        line: usize,
+        column: usize,
+        entry: String,
+    },
+    #[error("empty phonics at line {line}, column {column}")]
+    EmptyPhonics { line: usize, column: usize },
+    #[error("I/O error")]
+    Io(#[from] std::io::Error),
+    #[error("the model has no syllable to start a name with")]
+    EmptyModel,
+    #[error("no outgoing syllable starts with `{0}`")]
+    NoOutgoingSyllable(String),
+    #[error("no preceding syllable ends with `{0}`")]
+    NoPrecedingSyllable(String),
+    #[error("no phonetic connection is available from `{0}`")]
+    NoPhoneticConnection(String),
+    #[error("random value {0} is out of the range [0, 1)")]
+    InvalidRandomValue(f64),
+    #[error("the name did not end within {0} syllables")]
+    SyllableLimitExceeded(usize),
+    #[error("the maximum number of syllables {max} is less than the minimum {min}")]
+    InvalidSyllableRange { min: usize, max: usize },
+    #[error("the temperature must be positive and finite, got {0}")]
+    InvalidTemperature(f64),
+    #[error("top-k must be at least 1, got {0}")]
+    InvalidTopK(usize),
+    #[error("top-p must be in the range (0, 1], got {0}")]
+    InvalidTopP(f64),
+    #[error("the context order must be at least 1, got {0:?}")]
+    InvalidContextOrder(ContextOrder),
+    #[error("the file is of format version {found}, but only version {supported} is supported")]
+    IncompatibleVersion { found: u32, supported: u32 },
+    #[error("invalid file: {0}")]
+    InvalidFormat(String),
+    #[error("no new name was generated in {0} attempts")]
+    AttemptsExhausted(usize),
+    #[error("invalid smoothing parameter {0:?}")]
+    InvalidSmoothing(Smoothing),
+    #[error("invalid model {0:?}: the weight of the syllable chain must be in [0, 1)")]
+    InvalidModel(Model),
+    #[error("the weight must be positive and finite, got {0}")]
+    InvalidWeight(f64),
+    #[error(
+        "malformed weight `{entry}` at line {line}, column {column}: expected a positive number"
+    )]
+    MalformedWeight {
+        line: usize,
+        column: usize,
+        entry: String,
+    },
+    #[error("no name in the model satisfies the constraints")]
+    UnsatisfiableConstraints,
+    #[error("no syllable `{0}` in the dataset can be placed at the position")]
+    NoSuchSyllable(String),
+    #[error("the constraints are not supported in the backward generation")]
+    UnsupportedConstraints,
+    #[error("the back-off of the tags must be positive and finite, got {0}")]
+    InvalidTagBackoff(f64),
 }
 
-struct PhoneticConnectionBuilder {
-    conn: HashMap<char, HashMap<char, usize>>,
+/// Whether `weight` can be the weight of a name.
+fn is_valid_weight(weight: f64) -> bool {
+    weight.is_finite() && weight > 0.0
 }
 
-impl PhoneticConnectionBuilder {
-    fn new() -> PhoneticConnectionBuilder {
-        PhoneticConnectionBuilder {
-            conn: HashMap::new(),
-        }
-    }
-
-    fn add_char_pair(&mut self, incoming_char: char, outgoing_char: char) {
-        self.conn
-            .entry(incoming_char)
-            .or_default()
-            .entry(outgoing_char)
-            .and_modify(|e| *e += 1)
-            .or_insert(1);
-    }
-
-    fn build(self) -> PhoneticConnection {
-        let mut builder = SortedVecBuilder::new();
-        self.conn.iter().for_each(|(k, v)| {
-            let mut sum = 0;
-            for v2 in v.values() {
-                sum += v2;
-            }
-
-            let mut v = v.iter().collect::<Vec<(&char, &usize)>>();
-            v.sort_by(|a, b| a.0.cmp(b.0));
-
-            let mut set = SortedVecBuilder::new();
-            let mut prop = 0.0;
-
-            for (k2, v2) in v {
-                prop += *v2 as f64 / sum as f64;
-                set.push(prop, *k2);
-            }
-
-            let set = set.build();
-            builder.push(*k, set);
-        });
-
-        PhoneticConnection {
-            conn: builder.build(),
-        }
-    }
-}
-
-struct PhoneticConnection {
-    conn: SortedVec<char, SortedVec<f64, char>>,
+/// The builder for the NameGenerator.
+pub struct NameGeneratorBuilder {
+    names: Vec<Name>,
+    // weight of each name
+    weights: Vec<f64>,
+    // index of the dataset of each name
+    datasets: Vec<usize>,
+    order: ContextOrder,
+    smoothing: Smoothing,
+    model: Model,
+    termination: Termination,
 }
 
-impl PhoneticConnection {
-    fn extract_forward(&self, character: char, prop: f64) -> char {
-        let found = {
-            let set = &self.conn.find(character).1;
-            let found = set.find(prop);
-            found
-        };
-        found.1
+impl Default for NameGeneratorBuilder {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-/// The struct that represents the name.
-///  names are composed of syllables, and each syllable has a letter as `Letter`, and a phonetic representation as `Phonics`.
-///
-/// Example: Bedford -> Name::new(vec![("bed", "ˈbɛd"), ("ford", "fərd")])
-#[derive(Debug)]
-pub struct Name {
-    syllables: Vec<(Letter, Phonics)>,
-}
-
-impl Name {
-    pub fn new(syllables: Vec<(&str, &str)>) -> Result<Self, NameError> {
-        Self::from_string(
-            syllables
-                .iter()
-                .map(|(k, r)| (k.to_string(), r.to_string()))
-                .collect(),
-        )
-    }
-
-    pub fn from_string(syllables: Vec<(String, String)>) -> Result<Self, NameError> {
-        for syllable in &syllables {
-            if syllable.1.is_empty() {
-                return Err(NameError::EmptyString);
-            }
-        }
-        Ok(Self { syllables })
-    }
-
-    fn connection_pairs(&self) -> Vec<(char, char)> {
-        let mut pairs = vec![];
-        for i in 0..self.syllables.len() - 1 {
-            pairs.push((
-                self.syllables[i].1.chars().last().unwrap(),
-                self.syllables[i + 1].1.chars().next().unwrap(),
-            ))
+impl NameGeneratorBuilder {
+    pub fn new() -> Self {
+        Self {
+            names: vec![],
+            weights: vec![],
+            datasets: vec![],
+            order: ContextOrder::default(),
+            smoothing: Smoothing::default(),
+            model: Model::default(),
+            termination: Termination::default(),
         }
-        pairs
     }
 
-    fn last_char_of_syllable(&self, i: usize) -> char {
-        self.syllables[i].1.chars().last().unwrap()
+    /// Set the number of phonemes used as the context of the phonetic connection.
+    pub fn context_order(mut self, order: ContextOrder) -> Self {
+        self.order = order;
+        self
     }
 
-    pub fn content(&self) -> Letter {
-        self.syllables.iter().map(|p| p.0.clone()).collect()
+    /// Set the smoothing of the phonetic connection. No smoothing is applied by default.
+    pub fn smoothing(mut self, smoothing: Smoothing) -> Self {
+        self.smoothing = smoothing;
+        self
     }
 
-    pub fn script(&self) -> Phonics {
-        self.syllables.iter().map(|p| p.1.clone()).collect()
+    /// Set the model of the transitions between the syllables. The phonetic join is used by default.
+    pub fn model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
     }
 
-    pub fn syllables(&self) -> &Vec<(Letter, Phonics)> {
-        &self.syllables
+    /// Set how the generation decides to end the name. The structural termination is used by default.
+    pub fn termination(mut self, termination: Termination) -> Self {
+        self.termination = termination;
+        self
     }
-}
 
-/// The builder for the NameGenerator.
-pub struct NameGeneratorBuilder {
-    names: Vec<Name>,
-}
-
-impl Default for NameGeneratorBuilder {
-    fn default() -> Self {
-        Self::new()
+    pub fn add_name(self, name: Name) -> Self {
+        self.add_weighted_name(name, 1.0)
     }
-}
 
-impl NameGeneratorBuilder {
-    pub fn new() -> Self {
-        Self { names: vec![] }
+    pub fn bulk_add_names(self, names: Vec<Name>) -> Self {
+        self.bulk_add_weighted_names(names.into_iter().map(|name| (name, 1.0)).collect())
     }
 
-    pub fn add_name(mut self, name: Name) -> Self {
+    /// Add a name whose influence on the model is scaled by `weight`.
+    ///  The weight scales both the counts of its phonetic connections and the chance that its syllables are chosen.
+    ///  `add_name` is the same as the weight 1.
+    ///
+    /// Example: builder.add_weighted_name(london, 10.0).add_weighted_name(hamlet, 0.5)
+    pub fn add_weighted_name(mut self, name: Name, weight: f64) -> Self {
         self.names.push(name);
+        self.weights.push(weight);
+        self.datasets.push(0);
         self
     }
 
-    pub fn bulk_add_names(mut self, names: Vec<Name>) -> Self {
-        self.names.extend(names);
+    pub fn bulk_add_weighted_names(mut self, names: Vec<(Name, f64)>) -> Self {
+        for (name, weight) in names {
+            self.names.push(name);
+            self.weights.push(weight);
+            self.datasets.push(0);
+        }
         self
     }
 
-    pub fn build(self) -> NameGenerator {
-        let mut conn_builder = PhoneticConnectionBuilder::new();
+    /// Build the generator.
+    /// An error is returned if the names do not form a model that can generate any name.
+    pub fn build(self) -> Result<NameGenerator, NameError> {
+        if self.order.incoming == 0 || self.order.outgoing == 0 {
+            return Err(NameError::InvalidContextOrder(self.order));
+        }
+        if !self.smoothing.is_valid() {
+            return Err(NameError::InvalidSmoothing(self.smoothing));
+        }
+        if !self.model.is_valid() {
+            return Err(NameError::InvalidModel(self.model));
+        }
+        if let Some(weight) = self.weights.iter().find(|w| !is_valid_weight(**w)) {
+            return Err(NameError::InvalidWeight(*weight));
+        }
+        let mut conn_builder = PhoneticConnectionBuilder::new(self.order);
         let mut outgoing_tree = HashMap::new();
         let mut incoming_syllables = vec![];
+        let mut word_initial_syllables = vec![];
         let mut outgoing_syllables = vec![];
+        let mut push_outgoing = |outgoing_syllable: (usize, usize, ToRestore), key: PhonemeKey| {
+            outgoing_syllables.push(outgoing_syllable);
+            outgoing_tree
+                .entry(key)
+                .and_modify(|v: &mut Vec<usize>| v.push(outgoing_syllables.len() - 1))
+                .or_insert(vec![outgoing_syllables.len() - 1]);
+            outgoing_syllables.len() - 1
+        };
+        let mut successor_tree: HashMap<(Letter, Phonics), Vec<usize>> = HashMap::new();
+        // the backward connection runs on the reversed phonemes, so the orders are swapped
+        let mut backward_builder = PhoneticConnectionBuilder::new(ContextOrder {
+            incoming: self.order.outgoing,
+            outgoing: self.order.incoming,
+        });
+        let mut preceding_tree = HashMap::new();
+        let mut final_syllables = vec![];
+        let mut preceding_syllables = vec![];
+        let mut push_preceding = |preceding_syllable: (usize, usize, ToRestore),
+                                  key: PhonemeKey| {
+            preceding_syllables.push(preceding_syllable);
+            preceding_tree
+                .entry(key)
+                .and_modify(|v: &mut Vec<usize>| v.push(preceding_syllables.len() - 1))
+                .or_insert(vec![preceding_syllables.len() - 1]);
+        };
         self.names.iter().enumerate().for_each(|(ipn, name)| {
-            name.connection_pairs()
-                .iter()
-                .enumerate()
-                .for_each(|(ipc, pair)| {
-                    conn_builder.add_char_pair(pair.0, pair.1);
-                    if ipc == 0 {
-                        incoming_syllables.push((ipn, ipc));
-                    }
-                    let to_restore = ipc + 1 != name.syllables.len() - 1;
-                    outgoing_syllables.push((ipn, ipc + 1, to_restore));
-                    outgoing_tree
-                        .entry(pair.1)
-                        .and_modify(|v: &mut Vec<usize>| v.push(outgoing_syllables.len() - 1))
-                        .or_insert(vec![outgoing_syllables.len() - 1]);
-                });
+            let len = name.syllables().len();
+            // a single-syllable name is a complete name by itself, and can also end other names
+            if len == 1 {
+                let phonemes = name.phonemes_of_syllable(0);
+                incoming_syllables.push((ipn, 0, false));
+                push_outgoing((ipn, 0, false), conn_builder.outgoing_key(phonemes));
+                final_syllables.push((ipn, 0, false));
+                push_preceding(
+                    (ipn, 0, false),
+                    reversed(&backward_builder.outgoing_key(&reversed(phonemes))),
+                );
+                return;
+            }
+            (0..len).for_each(|ipc| {
+                let to_restore = ipc + 1 != len;
+                // the later words start only after a separator, so they are kept apart from the name starts
+                if ipc == 0 {
+                    incoming_syllables.push((ipn, ipc, to_restore));
+                    return;
+                }
+                if name.flags_of_syllable(ipc - 1).separator {
+                    word_initial_syllables.push((ipn, ipc, to_restore));
+                    return;
+                }
+                // the phonemes of a separator are empty, so the separators share the empty key
+                let phonemes = name.phonemes_of_syllable(ipc);
+                conn_builder.add_pair(
+                    name.phonemes_of_syllable(ipc - 1),
+                    phonemes,
+                    self.weights[ipn],
+                );
+                let index =
+                    push_outgoing((ipn, ipc, to_restore), conn_builder.outgoing_key(phonemes));
+                successor_tree
+                    .entry(name.syllables()[ipc - 1].clone())
+                    .or_default()
+                    .push(index);
+            });
+            // the mirror image of the above, for the backward generation
+            (0..len).for_each(|ipc| {
+                let to_extend = ipc != 0;
+                if name.flags_of_syllable(ipc).word_final {
+                    final_syllables.push((ipn, ipc, to_extend));
+                }
+                // the syllables before a separator are chosen from the final syllables
+                if ipc + 1 == len || name.flags_of_syllable(ipc + 1).separator {
+                    return;
+                }
+                let phonemes = reversed(name.phonemes_of_syllable(ipc));
+                backward_builder.add_pair(
+                    &reversed(name.phonemes_of_syllable(ipc + 1)),
+                    &phonemes,
+                    self.weights[ipn],
+                );
+                push_preceding(
+                    (ipn, ipc, to_extend),
+                    reversed(&backward_builder.outgoing_key(&phonemes)),
+                );
+            });
         });
 
-        NameGenerator {
+        let conn = conn_builder.build(
+            self.smoothing,
+            &outgoing_tree.keys().cloned().collect::<Vec<_>>(),
+        );
+        let backward_conn = backward_builder.build(
+            self.smoothing,
+            &preceding_tree
+                .keys()
+                .map(|key| reversed(key))
+                .collect::<Vec<_>>(),
+        );
+        let stop = StopModel::learn(&self.names, &self.weights);
+        let positions = PositionFrequency::learn(&self.names, &self.weights);
+        let generator = NameGenerator {
             names: self.names,
+            weights: self.weights,
+            datasets: self.datasets,
             incoming_syllables,
+            word_initial_syllables,
             outgoing_syllables,
             outgoing_tree,
-            conn: conn_builder.build(),
-        }
+            conn,
+            final_syllables,
+            preceding_syllables,
+            preceding_tree,
+            backward_conn,
+            model: self.model,
+            successor_tree,
+            termination: self.termination,
+            stop,
+            positions,
+            smoothing: self.smoothing,
+        };
+        generator.validate()?;
+        Ok(generator)
     }
 }
 
 /// The generator for the names.
+///  With the `serde` feature, a deserialized generator is validated as the one read by `load`.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "serde_format::UncheckedGenerator")
+)]
 pub struct NameGenerator {
     // list of the names
     names: Vec<Name>,
+    // weight of each name
+    weights: Vec<f64>,
+    // index of the dataset of each name
+    datasets: Vec<usize>,
     // syllables that can be the first syllable
-    incoming_syllables: Vec<(usize, usize)>,
+    incoming_syllables: Vec<(usize, usize, ToRestore)>,
+    // syllables that can start a word after a separator, besides the incoming syllables
+    word_initial_syllables: Vec<(usize, usize, ToRestore)>,
     // syllables that can be the next syllable
     outgoing_syllables: Vec<(usize, usize, ToRestore)>,
-    // list of the index of the outgoing_syllables which has the same first character
-    outgoing_tree: HashMap<char, Vec<usize>>,
-    // phonetic connection between the last character of the previous syllable and the first character of the next syllable
+    // list of the index of the outgoing_syllables which has the same first phonemes
+    #[cfg_attr(feature = "serde", serde(with = "serde_format::pairs"))]
+    outgoing_tree: HashMap<PhonemeKey, Vec<usize>>,
+    // phonetic connection between the last phonemes of the previous syllable and the first phonemes of the next syllable
     conn: PhoneticConnection,
+    // syllables that can be the last syllable
+    final_syllables: Vec<(usize, usize, ToRestore)>,
+    // syllables that can be the previous syllable, continued backward if `ToRestore`
+    preceding_syllables: Vec<(usize, usize, ToRestore)>,
+    // list of the index of the preceding_syllables which has the same last phonemes
+    #[cfg_attr(feature = "serde", serde(with = "serde_format::pairs"))]
+    preceding_tree: HashMap<PhonemeKey, Vec<usize>>,
+    // phonetic connection from the reversed first phonemes of the next syllable to the reversed last phonemes of the previous syllable
+    backward_conn: PhoneticConnection,
+    // model of the transitions between the syllables
+    model: Model,
+    // list of the index of the outgoing_syllables which follow the same syllable in the dataset
+    #[cfg_attr(feature = "serde", serde(with = "serde_format::pairs"))]
+    successor_tree: HashMap<(Letter, Phonics), Vec<usize>>,
+    // how the generation decides to end the name
+    termination: Termination,
+    // probability to end the name learned from the names and their weights, which is not written in the binary format
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stop: StopModel,
+    // total frequency of the positions of the syllables, by which the position pools are drawn, which is not written either
+    #[cfg_attr(feature = "serde", serde(skip))]
+    positions: PositionFrequency,
+    // smoothing of the phonetic connections, kept to build the generator again
+    smoothing: Smoothing,
 }
 
 /// The detailed information of the syllables.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SyllableInfo {
     /// The index of the name in the dataset
     pub name_index: usize,
     /// The index of the syllable in the name
     pub syllable_index: usize,
+    /// The index of the dataset of the name, which is the order of the sources of `NameGeneratorBuilder::mixed`,
+    ///  or 0 if the names were not mixed
+    pub dataset: usize,
+    /// The log-probability with which this syllable was chosen after the previous one,
+    ///  or before the next one in the backward generation
+    pub log_probability: f64,
+    /// The first phonemes of this syllable through which it was connected to the previous one,
+    ///  or `None` if it was chosen without the phonetic connection, like the first syllable
+    pub join: Option<Vec<Phoneme>>,
 }
 
 impl NameGenerator {
-    /// Generate a name with detailed information of the syllables.
-    /// Random number generator is required as argument `rand_fn`.
-    pub fn generate_verbose(
+    /// Learn the statistics of the names which are not written in the files, after the generator is read.
+    fn learn_statistics(&mut self) {
+        self.stop = StopModel::learn(&self.names, &self.weights);
+        self.positions = PositionFrequency::learn(&self.names, &self.weights);
+    }
+
+    /// Check that every step of the generation can be continued.
+    fn validate(&self) -> Result<(), NameError> {
+        if self.incoming_syllables.is_empty() {
+            return Err(NameError::EmptyModel);
+        }
+        if !self.smoothing.is_valid() {
+            return Err(NameError::InvalidSmoothing(self.smoothing));
+        }
+        if !self.model.is_valid() {
+            return Err(NameError::InvalidModel(self.model));
+        }
+        if self.weights.len() != self.names.len()
+            || !self.weights.iter().all(|w| is_valid_weight(*w))
+        {
+            return Err(NameError::InvalidFormat("invalid weights".to_string()));
+        }
+        if self.datasets.len() != self.names.len() {
+            return Err(NameError::InvalidFormat("invalid datasets".to_string()));
+        }
+        if !self.names.iter().all(Name::is_consistent) {
+            return Err(NameError::InvalidFormat("inconsistent name".to_string()));
+        }
+        let in_range = |(ipn, ipc, _): &(usize, usize, ToRestore)| {
+            self.names
+                .get(*ipn)
+                .is_some_and(|name| *ipc < name.syllables().len())
+        };
+        if !self.incoming_syllables.iter().all(in_range)
+            || !self.word_initial_syllables.iter().all(in_range)
+            || !self.outgoing_syllables.iter().all(in_range)
+            || !self.final_syllables.iter().all(in_range)
+            || !self.preceding_syllables.iter().all(in_range)
+            || self
+                .outgoing_tree
+                .values()
+                .flatten()
+                .any(|i| *i >= self.outgoing_syllables.len())
+            || self
+                .preceding_tree
+                .values()
+                .flatten()
+                .any(|i| *i >= self.preceding_syllables.len())
+            || self
+                .successor_tree
+                .values()
+                .flatten()
+                .any(|i| *i >= self.outgoing_syllables.len())
+        {
+            return Err(NameError::InvalidFormat(
+                "syllable index out of range".to_string(),
+            ));
+        }
+        let incoming_phonemes = self
+            .starting_syllables(true)
+            .chain(self.outgoing_syllables.iter())
+            .filter(|(ipn, ipc, to_restore)| {
+                *to_restore && !self.names[*ipn].flags_of_syllable(*ipc).separator
+            })
+            .map(|(ipn, ipc, _)| self.names[*ipn].phonemes_of_syllable(*ipc));
+        for phonemes in incoming_phonemes {
+            if !self.conn.has_incoming(phonemes) {
+                return Err(NameError::NoPhoneticConnection(phonemes.concat()));
+            }
+        }
+        for key in self.conn.outgoing_keys() {
+            if !self.outgoing_tree.contains_key(key) {
+                return Err(NameError::NoOutgoingSyllable(key.concat()));
+            }
+        }
+        let outgoing_phonemes = self
+            .final_syllables
+            .iter()
+            .chain(self.preceding_syllables.iter())
+            .filter(|(ipn, ipc, to_extend)| {
+                *to_extend && !self.names[*ipn].flags_of_syllable(*ipc).separator
+            })
+            .map(|(ipn, ipc, _)| self.names[*ipn].phonemes_of_syllable(*ipc));
+        for phonemes in outgoing_phonemes {
+            if !self.backward_conn.has_incoming(&reversed(phonemes)) {
+                return Err(NameError::NoPhoneticConnection(phonemes.concat()));
+            }
+        }
+        for key in self.backward_conn.outgoing_keys() {
+            let key = reversed(key);
+            if !self.preceding_tree.contains_key(&key) {
+                return Err(NameError::NoPrecedingSyllable(key.concat()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Choose one of the syllables in `list` by the weights of their names, steered by `progress` and reshaped by `options`.
+    ///  The syllables `following` another one in the name are drawn first by the pools of their positions.
+    ///  The probability of the chosen syllable is returned together.
+    fn choose<'a>(
         &self,
-        mut rand_fn: impl FnMut() -> f64,
-    ) -> (Letter, Phonics, Vec<SyllableInfo>) {
-        let query_next = |incoming_syllable: (usize, usize), p0: f64, p1: f64| {
-            let connection_syllable = self.conn.extract_forward(
-                self.names[incoming_syllable.0].last_char_of_syllable(incoming_syllable.1),
-                p0,
-            );
-            let outgoing_syllable_list = &self.outgoing_tree[&connection_syllable];
-            &self.outgoing_syllables
-                [outgoing_syllable_list[(p1 * outgoing_syllable_list.len() as f64) as usize]]
+        list: Vec<&'a (usize, usize, ToRestore)>,
+        following: bool,
+        options: &GenerationOptions,
+        progress: Progress,
+        draw: &mut dyn Draw,
+    ) -> Result<(&'a (usize, usize, ToRestore), f64), NameError> {
+        let weights = self.syllable_weights(&list, following, options, progress);
+        let i = draw.next_weighted(&weights)?;
+        Ok((list[i], weights[i] / weights.iter().sum::<f64>()))
+    }
+
+    /// The weights of the syllables in `list` to be chosen, which are the weights of their names,
+    ///  or the pooled ones if they are `following` another one in the name,
+    ///  multiplied by the probabilities to complete the constraints under `progress`, and reshaped by `options`.
+    fn syllable_weights(
+        &self,
+        list: &[&(usize, usize, ToRestore)],
+        following: bool,
+        options: &GenerationOptions,
+        progress: Progress,
+    ) -> Vec<f64> {
+        let weights = if following {
+            self.pooled_weights(list)
+        } else {
+            list.iter()
+                .map(|syllable| self.weights[syllable.0])
+                .collect()
         };
+        let weights = list
+            .iter()
+            .zip(weights)
+            .map(|(syllable, weight)| weight * self.completion(progress, syllable))
+            .collect::<Vec<_>>();
+        if options.reshapes() {
+            self.reshape_syllables(list, weights, options)
+        } else {
+            weights
+        }
+    }
 
-        let incoming_syllable =
-            &self.incoming_syllables[(rand_fn() * self.incoming_syllables.len() as f64) as usize];
-        let mut syllables_vec = vec![(incoming_syllable.0, incoming_syllable.1)];
+    /// The phonetic connections from the syllable `(ipn, ipc)` to the syllable at the position `count`,
+    ///  with the probabilities reshaped by `options`.
+    ///  The connections to the syllables preferred by `options` are used if any.
+    fn connections(
+        &self,
+        (ipn, ipc): (usize, usize),
+        count: usize,
+        options: &GenerationOptions,
+        progress: Progress,
+    ) -> Result<Vec<(&PhonemeKey, f64)>, NameError> {
+        let feasible =
+            |syllable: &(usize, usize, ToRestore)| self.completion(progress, syllable) > 0.0;
+        let flags = self.names[ipn].flags_of_syllable(ipc);
+        let allowed = |key: &PhonemeKey| flags.allows_next(key);
 
-        let mut restore_flag = true;
-        while restore_flag {
-            let (k, r, to_restore) =
-                query_next(syllables_vec[syllables_vec.len() - 1], rand_fn(), rand_fn());
-            syllables_vec.push((*k, *r));
-            restore_flag = *to_restore;
+        let incoming_phonemes = self.names[ipn].phonemes_of_syllable(ipc);
+        let mut candidates = self.conn.distribution(incoming_phonemes, |key| {
+            allowed(key)
+                && self.outgoing_tree.get(key).is_some_and(|list| {
+                    list.iter().any(|i| {
+                        let syllable = &self.outgoing_syllables[*i];
+                        feasible(syllable) && self.prefers(options, count, syllable)
+                    })
+                })
+        });
+        if candidates.is_empty() {
+            candidates = self.conn.distribution(incoming_phonemes, |key| {
+                allowed(key)
+                    && self.outgoing_tree.get(key).is_none_or(|list| {
+                        list.iter().any(|i| feasible(&self.outgoing_syllables[*i]))
+                    })
+            });
         }
+        if candidates.is_empty() {
+            return Err(NameError::NoPhoneticConnection(incoming_phonemes.concat()));
+        }
+        if progress.is_some() {
+            // steer toward the connections likely to complete the constraints
+            candidates.iter_mut().for_each(|(key, probability)| {
+                let list = self.outgoing_tree[*key]
+                    .iter()
+                    .map(|i| &self.outgoing_syllables[*i])
+                    .collect::<Vec<_>>();
+                let weights = self.pooled_weights(&list);
+                *probability *= list
+                    .iter()
+                    .zip(&weights)
+                    .map(|(syllable, weight)| weight * self.completion(progress, syllable))
+                    .sum::<f64>()
+                    / weights.iter().sum::<f64>();
+            });
+            let total = candidates.iter().map(|(_, p)| p).sum::<f64>();
+            candidates.iter_mut().for_each(|(_, p)| *p /= total);
+        }
+        Ok(options.reshape(candidates))
+    }
 
-        let syllable_info = syllables_vec
+    /// The syllables that can start a word, which are only the first syllables of the names unless `separated`.
+    pub(crate) fn starting_syllables(
+        &self,
+        separated: bool,
+    ) -> impl Iterator<Item = &(usize, usize, ToRestore)> {
+        let word_initial_syllables = if separated {
+            &self.word_initial_syllables[..]
+        } else {
+            &[]
+        };
+        self.incoming_syllables.iter().chain(word_initial_syllables)
+    }
+
+    /// The syllables starting a word preferred at the position `count`, after a separator if `separated`,
+    ///  among the ones that can complete the constraints under `progress`.
+    fn starting_list(
+        &self,
+        separated: bool,
+        count: usize,
+        options: &GenerationOptions,
+        progress: Progress,
+    ) -> Vec<&(usize, usize, ToRestore)> {
+        preferred(
+            self.starting_syllables(separated)
+                .filter(|syllable| self.completion(progress, syllable) > 0.0)
+                .collect(),
+            |syllable| self.prefers(options, count, syllable),
+        )
+    }
+
+    /// The outgoing syllables starting with `key` preferred at the position `count`,
+    ///  among the ones that can complete the constraints under `progress`.
+    fn outgoing_list(
+        &self,
+        key: &PhonemeKey,
+        count: usize,
+        options: &GenerationOptions,
+        progress: Progress,
+    ) -> Result<Vec<&(usize, usize, ToRestore)>, NameError> {
+        Ok(preferred(
+            self.outgoing_tree
+                .get(key)
+                .ok_or_else(|| NameError::NoOutgoingSyllable(key.concat()))?
+                .iter()
+                .map(|i| &self.outgoing_syllables[*i])
+                .filter(|syllable| self.completion(progress, syllable) > 0.0)
+                .collect(),
+            |syllable| self.prefers(options, count, syllable),
+        ))
+    }
+
+    /// Reshape `weights` of the syllables in `list` by `options`.
+    ///  The syllables with the same letter, phonics and role are reshaped together as one candidate,
+    ///  sharing its probability by their weights.
+    ///  The temperature applies to the pools of the medial and the final syllables by their total weights,
+    ///  and to the candidates within each pool, so a large pool does not crowd out the other.
+    ///  Then the candidates of both pools are truncated together by top-k and top-p.
+    ///  With `Termination::Learned`, the syllables are tempered as one pool.
+    fn reshape_syllables(
+        &self,
+        list: &[&(usize, usize, ToRestore)],
+        weights: Vec<f64>,
+        options: &GenerationOptions,
+    ) -> Vec<f64> {
+        // the pool and the total weight of each candidate
+        let mut groups: Vec<(Option<Position>, f64)> = vec![];
+        let mut group_of = HashMap::new();
+        let group_indices = list
             .iter()
-            .map(|p| SyllableInfo {
-                name_index: p.0,
-                syllable_index: p.1,
+            .zip(&weights)
+            .map(|((ipn, ipc, to_restore), weight)| {
+                let name = &self.names[*ipn];
+                let role = self.role_key(*to_restore);
+                let key = (
+                    &name.syllables()[*ipc],
+                    name.flags_of_syllable(*ipc).separator,
+                    role,
+                );
+                let group = *group_of.entry(key).or_insert_with(|| {
+                    groups.push((role.map(Position::of_next), 0.0));
+                    groups.len() - 1
+                });
+                groups[group].1 += weight;
+                group
             })
-            .collect::<Vec<SyllableInfo>>();
+            .collect::<Vec<_>>();
+        let total = groups.iter().map(|(_, weight)| weight).sum::<f64>();
 
-        let content = syllables_vec
-            .iter()
-            .map(|p| self.names[p.0].syllables[p.1].0.clone())
-            .collect::<Vec<Letter>>()
-            .join("");
-        let script = syllables_vec
+        let mut pools: Vec<(Option<Position>, f64)> = vec![];
+        for (pool, weight) in &groups {
+            match pools.iter_mut().find(|(p, _)| p == pool) {
+                Some((_, pool_weight)) => *pool_weight += weight / total,
+                None => pools.push((*pool, weight / total)),
+            }
+        }
+        let mut tempered = vec![0.0; groups.len()];
+        for (pool, probability) in options.tempered(pools) {
+            let members = (0..groups.len())
+                .filter(|i| groups[*i].0 == pool)
+                .collect::<Vec<_>>();
+            let pool_total = members.iter().map(|i| groups[*i].1).sum::<f64>();
+            let candidates = members
+                .into_iter()
+                .map(|i| (i, groups[i].1 / pool_total))
+                .collect();
+            for (i, p) in options.tempered(candidates) {
+                tempered[i] = probability * p;
+            }
+        }
+
+        let mut reshaped = vec![0.0; groups.len()];
+        options
+            .truncated(tempered.into_iter().enumerate().collect())
+            .into_iter()
+            .for_each(|(group, p)| reshaped[group] = p);
+        group_indices
             .iter()
-            .map(|p| self.names[p.0].syllables[p.1].1.clone())
-            .collect::<Vec<Phonics>>()
-            .join("");
+            .zip(weights)
+            .map(|(group, weight)| reshaped[*group] * weight / groups[*group].1)
+            .collect()
+    }
+
+    /// Generate a name under `options`, with detailed information of the syllables.
+    /// Random number generator is required as argument `rand_fn`, which must return values in `[0, 1)`.
+    /// The constraints of `options` are analyzed on every call, so `prepare` them to generate many names.
+    pub fn generate_with_options(
+        &self,
+        options: &GenerationOptions,
+        rand_fn: impl FnMut() -> f64,
+    ) -> Result<GeneratedName<'_>, NameError> {
+        self.generate_with_draw(options, &mut FnDraw(rand_fn))
+    }
+
+    /// Generate a name under `options`, drawing the random values from `draw`.
+    ///  The options are prepared on every call, so use `prepare` to generate many constrained names.
+    fn generate_with_draw(
+        &self,
+        options: &GenerationOptions,
+        draw: &mut dyn Draw,
+    ) -> Result<GeneratedName<'_>, NameError> {
+        self.prepare(options)?.generate_with_draw(draw)
+    }
+
+    /// Generate a name under `options` prepared by `steering`, drawing the random values from `draw`.
+    pub(crate) fn generate_steered(
+        &self,
+        options: &GenerationOptions,
+        steering: Option<&Steering>,
+        draw: &mut dyn Draw,
+    ) -> Result<GeneratedName<'_>, NameError> {
+        let mut progress = steering.map(|steering| (steering, MatchState::default()));
+        let list = self.starting_list(false, 1, options, progress);
+        let (incoming_syllable, probability) = self.choose(list, false, options, progress, draw)?;
+        let (incoming_syllable, role_probability) =
+            self.decide(incoming_syllable, 1, options, progress, draw)?;
+        progress = advance(progress, &incoming_syllable)?;
+        let mut syllable_info =
+            vec![self.syllable_info(&incoming_syllable, probability * role_probability, None)];
+        self.continue_forward(
+            &mut syllable_info,
+            incoming_syllable.2,
+            options,
+            progress,
+            draw,
+        )?;
+        Ok(GeneratedName::new(&self.names, syllable_info))
+    }
+
+    /// Append the syllables to `syllable_info` until the name ends, if `restore_flag` is set.
+    pub(crate) fn continue_forward(
+        &self,
+        syllable_info: &mut Vec<SyllableInfo>,
+        mut restore_flag: ToRestore,
+        options: &GenerationOptions,
+        mut progress: Progress,
+        draw: &mut dyn Draw,
+    ) -> Result<(), NameError> {
+        while restore_flag {
+            if syllable_info.len() >= options.syllable_limit {
+                return Err(NameError::SyllableLimitExceeded(options.syllable_limit));
+            }
+            let last = &syllable_info[syllable_info.len() - 1];
+            let count = syllable_info.len() + 1;
+            let (syllable, probability, join) = self.query_next(
+                (last.name_index, last.syllable_index),
+                count,
+                options,
+                progress,
+                draw,
+            )?;
+            let (syllable, role_probability) =
+                self.decide(syllable, count, options, progress, draw)?;
+            progress = advance(progress, &syllable)?;
+            syllable_info.push(self.syllable_info(&syllable, probability * role_probability, join));
+            restore_flag = syllable.2;
+        }
+        Ok(())
+    }
 
-        (content, script, syllable_info)
+    /// The information of `syllable` chosen by `probability` through the phonemes of `join`.
+    pub(crate) fn syllable_info(
+        &self,
+        &(ipn, ipc, _): &(usize, usize, ToRestore),
+        probability: f64,
+        join: Option<&PhonemeKey>,
+    ) -> SyllableInfo {
+        SyllableInfo {
+            name_index: ipn,
+            syllable_index: ipc,
+            dataset: self.datasets[ipn],
+            log_probability: probability.ln(),
+            join: join.cloned(),
+        }
+    }
+
+    /// Choose the syllable following the syllable `(ipn, ipc)` at the position `count`.
+    fn query_next(
+        &self,
+        (ipn, ipc): (usize, usize),
+        count: usize,
+        options: &GenerationOptions,
+        progress: Progress,
+        draw: &mut dyn Draw,
+    ) -> Result<Step<'_>, NameError> {
+        let p0 = draw.next_f64()?;
+        // a new word is started after a separator
+        if self.names[ipn].flags_of_syllable(ipc).separator {
+            let list = self.starting_list(true, count, options, progress);
+            let (incoming_syllable, probability) =
+                self.choose(list, true, options, progress, draw)?;
+            return Ok((incoming_syllable, probability, None));
+        }
+        if self.successors((ipn, ipc)).is_some() {
+            let candidates = self.interpolated((ipn, ipc), count, options, progress)?;
+            let mut cumulative = 0.0;
+            let (outgoing_syllable, probability) = *candidates
+                .iter()
+                .find(|(_, p)| {
+                    cumulative += p;
+                    cumulative >= p0
+                })
+                .unwrap_or(&candidates[candidates.len() - 1]);
+            return Ok((
+                outgoing_syllable,
+                probability,
+                Some(self.join_key(outgoing_syllable)),
+            ));
+        }
+        let connections = self.connections((ipn, ipc), count, options, progress)?;
+        let (connection_syllable, connection_probability) =
+            connection::extract_forward(&connections, p0);
+        let outgoing_syllable_list =
+            self.outgoing_list(connection_syllable, count, options, progress)?;
+        let (outgoing_syllable, probability) =
+            self.choose(outgoing_syllable_list, true, options, progress, draw)?;
+        Ok((
+            outgoing_syllable,
+            connection_probability * probability,
+            Some(connection_syllable),
+        ))
+    }
+
+    /// Generate the `index`-th name of `seed` under `options`, with detailed information of the syllables.
+    /// The name depends only on the generator, `options`, `seed` and `index`, drawn by the built-in `random::SplitMix64`.
+    /// So it is reproducible across runs and platforms, without generating the preceding names.
+    pub fn generate_seeded(
+        &self,
+        options: &GenerationOptions,
+        seed: u64,
+        index: u64,
+    ) -> Result<GeneratedName<'_>, NameError> {
+        self.prepare(options)?.generate_seeded(seed, index)
+    }
+
+    /// Generate a name under `options` with `rng`, with detailed information of the syllables.
+    /// The syllables are chosen by integers drawn from `rng`, so the choice is not biased by the truncation of floats.
+    #[cfg(feature = "rand")]
+    pub fn generate_with_options_and_rng<R: rand::Rng + ?Sized>(
+        &self,
+        options: &GenerationOptions,
+        rng: &mut R,
+    ) -> Result<GeneratedName<'_>, NameError> {
+        self.generate_with_draw(options, &mut draw::RngDraw(rng))
+    }
+
+    /// Generate a name with `rng`, with detailed information of the syllables.
+    ///
+    /// # Panics
+    /// Panics if the name does not end within `DEFAULT_SYLLABLE_LIMIT` syllables.
+    #[cfg(feature = "rand")]
+    pub fn generate_verbose_with_rng<R: rand::Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> GeneratedName<'_> {
+        self.generate_with_options_and_rng(&GenerationOptions::default(), rng)
+            .unwrap()
+    }
+
+    /// Generate a name with `rng`.
+    ///
+    /// # Panics
+    /// Panics if the name does not end within `DEFAULT_SYLLABLE_LIMIT` syllables.
+    #[cfg(feature = "rand")]
+    pub fn generate_with_rng<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> (Letter, Phonics) {
+        let name = self.generate_verbose_with_rng(rng);
+        (name.content(), name.script())
+    }
+
+    /// Generate a name with detailed information of the syllables.
+    /// Random number generator is required as argument `rand_fn`, which must return values in `[0, 1)`.
+    pub fn try_generate_verbose(
+        &self,
+        rand_fn: impl FnMut() -> f64,
+    ) -> Result<GeneratedName<'_>, NameError> {
+        self.generate_with_options(&GenerationOptions::default(), rand_fn)
     }
 
     /// Generate a name.
+    /// Random number generator is required as argument `rand_fn`, which must return values in `[0, 1)`.
+    pub fn try_generate(
+        &self,
+        rand_fn: impl FnMut() -> f64,
+    ) -> Result<(Letter, Phonics), NameError> {
+        let name = self.try_generate_verbose(rand_fn)?;
+        Ok((name.content(), name.script()))
+    }
+
+    /// Generate a name with detailed information of the syllables.
     /// Random number generator is required as argument `rand_fn`.
+    ///
+    /// # Panics
+    /// Panics if `rand_fn` returns a value out of `[0, 1)`, or if the name does not end within `DEFAULT_SYLLABLE_LIMIT` syllables.
+    /// Use `try_generate_verbose` to handle them as errors.
+    pub fn generate_verbose(&self, rand_fn: impl FnMut() -> f64) -> GeneratedName<'_> {
+        self.try_generate_verbose(rand_fn).unwrap()
+    }
+
+    /// Generate a name.
+    /// Random number generator is required as argument `rand_fn`.
+    ///
+    /// # Panics
+    /// Panics if `rand_fn` returns a value out of `[0, 1)`, or if the name does not end within `DEFAULT_SYLLABLE_LIMIT` syllables.
+    /// Use `try_generate` to handle them as errors.
     pub fn generate(&self, rand_fn: impl FnMut() -> f64) -> (Letter, Phonics) {
-        let (content, script, _) = self.generate_verbose(rand_fn);
-        (content, script)
+        self.try_generate(rand_fn).unwrap()
+    }
+
+    /// Create an infinite iterator of the generated names.
+    /// Random number generator is required as argument `rand_fn`, which must return values in `[0, 1)`.
+    pub fn iter<F: FnMut() -> f64>(&self, rand_fn: F) -> Names<'_, F> {
+        Names::new(self, rand_fn)
+    }
+
+    /// Generate `n` names and return the one scored the highest by `evaluator`, or `None` if all of them are rejected.
+    /// Random number generator is required as argument `rand_fn`, which must return values in `[0, 1)`.
+    pub fn best_of(
+        &self,
+        n: usize,
+        evaluator: &dyn NameEvaluator,
+        mut rand_fn: impl FnMut() -> f64,
+    ) -> Result<Option<GeneratedName<'_>>, NameError> {
+        let mut best = None;
+        let mut best_score = f64::NEG_INFINITY;
+        for _ in 0..n {
+            let name = self.try_generate_verbose(&mut rand_fn)?;
+            let Some(score) = evaluator.evaluate(&name) else {
+                continue;
+            };
+            if best.is_none() || score >= best_score {
+                best = Some(name);
+                best_score = score;
+            }
+        }
+        Ok(best)
+    }
+
+    /// Compute the log-likelihood of `name` under the model, or `None` if the model can never generate it.
+    ///  Each step is scored by the choice of the start syllable, the phonetic connection and the choice of the outgoing syllable,
+    ///  counting every syllable in the dataset with the same letter and phonics by the weight of its name.
+    ///  With `Model::SyllableChain`, the transitions between the syllables are interpolated as in the generation.
+    ///  With `Termination::Learned`, the syllables are counted regardless of their positions,
+    ///  and each step is also scored by the probability to end or continue the name there.
+    ///  `name` should be split into phonemes by the same tokenizer as the dataset.
+    pub fn score(&self, name: &Name) -> Option<f64> {
+        let len = name.syllables().len();
+        // the weighted fraction of `list` that has the syllable `i` of `name`, continuing the name as `name` does,
+        //  where the syllables following another one are weighted by the pools of their positions
+        let fraction = |list: Vec<&(usize, usize, ToRestore)>, i: usize| {
+            let weights = if i == 0 {
+                list.iter().map(|(ipn, _, _)| self.weights[*ipn]).collect()
+            } else {
+                self.pooled_weights(&list)
+            };
+            let (mut matched, mut total) = (0.0, 0.0);
+            list.iter()
+                .zip(weights)
+                .for_each(|((ipn, ipc, to_restore), weight)| {
+                    total += weight;
+                    let source = &self.names[*ipn];
+                    if self
+                        .role_key(*to_restore)
+                        .is_none_or(|to_restore| to_restore == (i + 1 != len))
+                        && source.syllables()[*ipc] == name.syllables()[i]
+                        && source.flags_of_syllable(*ipc).separator
+                            == name.flags_of_syllable(i).separator
+                    {
+                        matched += weight;
+                    }
+                });
+            if total == 0.0 {
+                0.0
+            } else {
+                matched / total
+            }
+        };
+        let outgoing = |list: &[usize]| {
+            list.iter()
+                .map(|j| &self.outgoing_syllables[*j])
+                .collect::<Vec<_>>()
+        };
+
+        let mut log_probability = 0.0;
+        for i in 0..len {
+            let probability = if i == 0 || name.flags_of_syllable(i - 1).separator {
+                fraction(self.starting_syllables(i != 0).collect(), i)
+            } else {
+                let flags = name.flags_of_syllable(i - 1);
+                let key = self.conn.outgoing_key(name.phonemes_of_syllable(i));
+                let join = self.outgoing_tree.get(&key).map_or(0.0, |list| {
+                    self.conn
+                        .probability(name.phonemes_of_syllable(i - 1), &key, |key| {
+                            flags.allows_next(key)
+                        })
+                        * fraction(outgoing(list), i)
+                });
+                let chain_weight = self.model.chain_weight();
+                match self.successor_tree.get(&name.syllables()[i - 1]) {
+                    Some(list) if chain_weight > 0.0 => {
+                        (1.0 - chain_weight) * join + chain_weight * fraction(outgoing(list), i)
+                    }
+                    _ => join,
+                }
+            };
+            let probability = probability * self.role_probability(name, i);
+            if probability <= 0.0 {
+                return None;
+            }
+            log_probability += probability.ln();
+        }
+        Some(log_probability)
+    }
+
+    /// Write the generator in the compact binary format.
+    pub fn save(&self, mut writer: impl Write) -> Result<(), NameError> {
+        binary::write_header(&mut writer)?;
+        self.encode(&mut writer)
+    }
+
+    /// Read a generator written by `save`.
+    ///  An error is returned if the file is of another format version, or is broken.
+    pub fn load(mut reader: impl Read) -> Result<Self, NameError> {
+        binary::read_header(&mut reader)?;
+        let generator = Self::decode(&mut reader)?;
+        generator.validate()?;
+        Ok(generator)
     }
 
     /// Get the list of the names as reference
     pub fn names(&self) -> &Vec<Name> {
         &self.names
     }
+
+    /// Get the weight of each name
+    pub fn weights(&self) -> &Vec<f64> {
+        &self.weights
+    }
+
+    /// Get the index of the dataset of each name
+    pub fn datasets(&self) -> &Vec<usize> {
+        &self.datasets
+    }
+}
+
+impl Encode for NameGenerator {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.names.encode(writer)?;
+        self.weights.encode(writer)?;
+        self.datasets.encode(writer)?;
+        self.incoming_syllables.encode(writer)?;
+        self.word_initial_syllables.encode(writer)?;
+        self.outgoing_syllables.encode(writer)?;
+        self.outgoing_tree.encode(writer)?;
+        self.conn.encode(writer)?;
+        self.final_syllables.encode(writer)?;
+        self.preceding_syllables.encode(writer)?;
+        self.preceding_tree.encode(writer)?;
+        self.backward_conn.encode(writer)?;
+        self.model.encode(writer)?;
+        self.successor_tree.encode(writer)?;
+        self.termination.encode(writer)?;
+        self.smoothing.encode(writer)
+    }
+}
+
+impl Decode for NameGenerator {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        let mut generator = Self {
+            names: Vec::decode(reader)?,
+            weights: Vec::decode(reader)?,
+            datasets: Vec::decode(reader)?,
+            incoming_syllables: Vec::decode(reader)?,
+            word_initial_syllables: Vec::decode(reader)?,
+            outgoing_syllables: Vec::decode(reader)?,
+            outgoing_tree: HashMap::decode(reader)?,
+            conn: PhoneticConnection::decode(reader)?,
+            final_syllables: Vec::decode(reader)?,
+            preceding_syllables: Vec::decode(reader)?,
+            preceding_tree: HashMap::decode(reader)?,
+            backward_conn: PhoneticConnection::decode(reader)?,
+            model: Model::decode(reader)?,
+            successor_tree: HashMap::decode(reader)?,
+            termination: Termination::decode(reader)?,
+            stop: StopModel::default(),
+            positions: PositionFrequency::default(),
+            smoothing: Smoothing::decode(reader)?,
+        };
+        generator.learn_statistics();
+        Ok(generator)
+    }
+}
+
+/// The generator of a few names, shared by the tests.
+#[cfg(test)]
+pub(crate) fn sample_generator() -> NameGenerator {
+    NameGeneratorBuilder::new()
+        .add_name(Name::new(vec![("bed", "bɛd"), ("ford", "fərd")]).unwrap())
+        .add_name(Name::new(vec![("brad", "bræd"), ("ford", "fərd")]).unwrap())
+        .add_name(Name::new(vec![("ox", "ɒks"), ("ford", "fərd")]).unwrap())
+        .add_name(Name::new(vec![("ox", "ɒks"), ("ley", "li")]).unwrap())
+        .add_weighted_name(
+            Name::new(vec![("brad", "bræd"), ("ley", "li")]).unwrap(),
+            2.0,
+        )
+        .build()
+        .unwrap()
 }
@@ -0,0 +1,60 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{is_valid_weight, NameGenerator, NameGeneratorBuilder};
+
+impl NameGeneratorBuilder {
+    /// Create a builder of the names of `sources` mixed by their weights.
+    ///  The weights of the names of each source are scaled so that the source influences the phonetic connections
+    ///  and the choice of the syllables in proportion to its weight, regardless of the number and the weights of its names.
+    ///  The sources are numbered in the order as `SyllableInfo::dataset`, following the datasets of the sources mixed before.
+    ///  The settings of the sources are not inherited, and `build` fails with `NameError::InvalidWeight` if a weight is not positive and finite.
+    ///
+    /// Example: NameGeneratorBuilder::mixed(vec![(england, 0.9), (california, 0.1)])
+    pub fn mixed(sources: Vec<(NameGeneratorBuilder, f64)>) -> Self {
+        // the total weight is kept, so that the smoothing works as on the sources
+        let total = sources
+            .iter()
+            .flat_map(|(source, _)| &source.weights)
+            .sum::<f64>();
+        let total_mixing = sources.iter().map(|(_, weight)| weight).sum::<f64>();
+        let mut builder = Self::new();
+        let mut offset = 0;
+        for (source, weight) in sources {
+            let dataset_count = source.datasets.iter().max().map_or(1, |max| max + 1);
+            let scale = weight / total_mixing * total / source.weights.iter().sum::<f64>();
+            for ((name, name_weight), dataset) in source
+                .names
+                .into_iter()
+                .zip(source.weights)
+                .zip(source.datasets)
+            {
+                builder.names.push(name);
+                // an invalid weight is kept to be reported by `build`
+                builder.weights.push(if is_valid_weight(weight) {
+                    name_weight * scale
+                } else {
+                    weight
+                });
+                builder.datasets.push(offset + dataset);
+            }
+            offset += dataset_count;
+        }
+        builder
+    }
+}
+
+impl From<NameGenerator> for NameGeneratorBuilder {
+    /// The builder of the names of `generator` with their weights and datasets, to be mixed with the others or built again.
+    ///  The settings of the generator are not inherited.
+    fn from(generator: NameGenerator) -> Self {
+        let mut builder = Self::new();
+        builder.names = generator.names;
+        builder.weights = generator.weights;
+        builder.datasets = generator.datasets;
+        builder
+    }
+}
@@ -0,0 +1,319 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::io::{Read, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::binary::{invalid, Decode, Encode};
+use crate::tokenizer::{CharTokenizer, Phoneme, Tokenizer, STRESS_MARKER};
+use crate::{Letter, NameError, PhonemeKey, Phonics};
+
+/// The role of a syllable in the name.
+///  `word_initial` and `word_final` are always set for the syllables at the edges of the words.
+///
+/// Example: Tunbridge Wells -> (Tun: word_initial, stressed) (bridge: word_final) ( : separator) (Wells: word_initial, word_final, stressed)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SyllableFlags {
+    /// The syllable can start a word
+    pub word_initial: bool,
+    /// The syllable can end a word
+    pub word_final: bool,
+    /// The syllable separates words, like the space in `Tunbridge Wells`
+    pub separator: bool,
+    /// The syllable has the primary stress
+    pub stressed: bool,
+}
+
+impl SyllableFlags {
+    /// The flags of a word separator.
+    pub fn separator() -> Self {
+        Self {
+            separator: true,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the syllable can be followed by the one starting with `key`.
+    ///  The words can be separated, by the separator of the empty key, only after a word-final syllable.
+    pub(crate) fn allows_next(&self, key: &PhonemeKey) -> bool {
+        self.word_final || !key.is_empty()
+    }
+
+    /// Whether the syllable can be preceded by the one ending with `key`.
+    ///  The words can be separated, by the separator of the empty key, only before a word-initial syllable.
+    pub(crate) fn allows_previous(&self, key: &PhonemeKey) -> bool {
+        self.word_initial || !key.is_empty()
+    }
+}
+
+/// The struct that represents the name.
+///  names are composed of syllables, and each syllable has a letter as `Letter`, and a phonetic representation as `Phonics`.
+///
+/// Example: Bedford -> Name::new(vec![("bed", "ˈbɛd"), ("ford", "fərd")])
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedName"))]
+pub struct Name {
+    syllables: Vec<(Letter, Phonics)>,
+    // phonemes of each syllable, which are empty for the separators
+    phonemes: Vec<Vec<Phoneme>>,
+    // role of each syllable
+    flags: Vec<SyllableFlags>,
+    // categories of the name, sorted without duplicates
+    tags: Vec<String>,
+}
+
+impl Name {
+    pub fn new(syllables: Vec<(&str, &str)>) -> Result<Self, NameError> {
+        Self::from_string(
+            syllables
+                .iter()
+                .map(|(k, r)| (k.to_string(), r.to_string()))
+                .collect(),
+        )
+    }
+
+    /// Create a name whose phonics are split into phonemes by `CharTokenizer`.
+    pub fn from_string(syllables: Vec<(String, String)>) -> Result<Self, NameError> {
+        Self::from_string_with_tokenizer(syllables, &CharTokenizer)
+    }
+
+    /// Create a name whose phonics are split into phonemes by `tokenizer`.
+    ///  A syllable is flagged as `stressed` if its phonics contains the primary stress marker `ˈ`.
+    pub fn from_string_with_tokenizer(
+        syllables: Vec<(String, String)>,
+        tokenizer: &dyn Tokenizer,
+    ) -> Result<Self, NameError> {
+        Self::from_syllables(
+            syllables
+                .into_iter()
+                .map(|(letter, phonics)| {
+                    let flags = SyllableFlags {
+                        stressed: phonics.contains(STRESS_MARKER),
+                        ..Default::default()
+                    };
+                    (letter, phonics, flags)
+                })
+                .collect(),
+            tokenizer,
+        )
+    }
+
+    /// Create a name from syllables with explicit flags.
+    ///  The letter and the phonics of a separator are rendered as they are, and the phonics may be empty.
+    ///
+    /// Example: Tunbridge Wells -> Name::from_syllables(vec![
+    ///     ("Tun", "ˈtʌn", flags), ("bridge", "brɪdʒ", flags), (" ", " ", SyllableFlags::separator()), ("Wells", "ˈwɛlz", flags)
+    /// ], &IpaTokenizer)
+    pub fn from_syllables(
+        syllables: Vec<(String, String, SyllableFlags)>,
+        tokenizer: &dyn Tokenizer,
+    ) -> Result<Self, NameError> {
+        if syllables.is_empty() {
+            return Err(NameError::EmptyName);
+        }
+        let is_separator = |i: usize| syllables.get(i).is_some_and(|s| s.2.separator);
+        if is_separator(0)
+            || is_separator(syllables.len() - 1)
+            || (1..syllables.len()).any(|i| is_separator(i - 1) && is_separator(i))
+        {
+            return Err(NameError::MisplacedSeparator);
+        }
+
+        let mut phonemes = vec![];
+        let mut flags = vec![];
+        for (i, (_, phonics, syllable_flags)) in syllables.iter().enumerate() {
+            let mut syllable_flags = *syllable_flags;
+            if syllable_flags.separator {
+                phonemes.push(vec![]);
+                flags.push(syllable_flags);
+                continue;
+            }
+            if phonics.is_empty() {
+                return Err(NameError::EmptyString);
+            }
+            let tokens = tokenizer.tokenize(phonics);
+            if tokens.is_empty() {
+                return Err(NameError::NoPhoneme(phonics.clone()));
+            }
+            phonemes.push(tokens);
+            syllable_flags.word_initial |= i == 0 || is_separator(i - 1);
+            syllable_flags.word_final |= i == syllables.len() - 1 || is_separator(i + 1);
+            flags.push(syllable_flags);
+        }
+
+        Ok(Self {
+            syllables: syllables
+                .into_iter()
+                .map(|(letter, phonics, _)| (letter, phonics))
+                .collect(),
+            phonemes,
+            flags,
+            tags: vec![],
+        })
+    }
+
+    /// Create a name from the parts already checked to be consistent.
+    pub(crate) fn from_parts(
+        syllables: Vec<(Letter, Phonics)>,
+        phonemes: Vec<Vec<Phoneme>>,
+        flags: Vec<SyllableFlags>,
+    ) -> Self {
+        Self {
+            syllables,
+            phonemes,
+            flags,
+            tags: vec![],
+        }
+    }
+
+    /// Set the categories of the name, like the region, the feature type or the gender,
+    ///  on which `NameGenerator::conditioned` conditions the generation.
+    ///
+    /// Example: Name::new(vec![("Tha", "ˈtɛ"), ("mes", "mz")])?.with_tags(&["river"])
+    pub fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self.tags.sort();
+        self.tags.dedup();
+        self
+    }
+
+    /// Whether the parts of the name are consistent, as the ones created by the constructors.
+    ///  Only the separators have no phoneme, and they are between two syllables.
+    pub(crate) fn is_consistent(&self) -> bool {
+        let len = self.syllables.len();
+        len != 0
+            && self.phonemes.len() == len
+            && self.flags.len() == len
+            && (0..len).all(|i| self.flags[i].separator == self.phonemes[i].is_empty())
+            && !self.flags[0].separator
+            && !self.flags[len - 1].separator
+            && self
+                .flags
+                .windows(2)
+                .all(|pair| !(pair[0].separator && pair[1].separator))
+            && self.tags.windows(2).all(|pair| pair[0] < pair[1])
+    }
+
+    /// The name itself if its parts are consistent, or an error as a broken file.
+    fn checked(self) -> Result<Self, NameError> {
+        if self.is_consistent() {
+            Ok(self)
+        } else {
+            Err(invalid("inconsistent name"))
+        }
+    }
+
+    pub(crate) fn phonemes_of_syllable(&self, i: usize) -> &[Phoneme] {
+        &self.phonemes[i]
+    }
+
+    pub(crate) fn flags_of_syllable(&self, i: usize) -> SyllableFlags {
+        self.flags[i]
+    }
+
+    pub fn content(&self) -> Letter {
+        self.syllables.iter().map(|p| p.0.clone()).collect()
+    }
+
+    pub fn script(&self) -> Phonics {
+        self.syllables.iter().map(|p| p.1.clone()).collect()
+    }
+
+    pub fn syllables(&self) -> &Vec<(Letter, Phonics)> {
+        &self.syllables
+    }
+
+    /// Get the phonemes of each syllable
+    pub fn phonemes(&self) -> &Vec<Vec<Phoneme>> {
+        &self.phonemes
+    }
+
+    /// Get the flags of each syllable
+    pub fn flags(&self) -> &Vec<SyllableFlags> {
+        &self.flags
+    }
+
+    /// Get the tags of the name in the sorted order
+    pub fn tags(&self) -> &Vec<String> {
+        &self.tags
+    }
+
+    /// Whether the name has all of `tags`.
+    pub fn has_tags(&self, tags: &[&str]) -> bool {
+        tags.iter()
+            .all(|tag| self.tags.binary_search_by(|t| t.as_str().cmp(tag)).is_ok())
+    }
+}
+
+impl Encode for SyllableFlags {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.word_initial.encode(writer)?;
+        self.word_final.encode(writer)?;
+        self.separator.encode(writer)?;
+        self.stressed.encode(writer)
+    }
+}
+
+impl Decode for SyllableFlags {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Ok(Self {
+            word_initial: bool::decode(reader)?,
+            word_final: bool::decode(reader)?,
+            separator: bool::decode(reader)?,
+            stressed: bool::decode(reader)?,
+        })
+    }
+}
+
+impl Encode for Name {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.syllables.encode(writer)?;
+        self.phonemes.encode(writer)?;
+        self.flags.encode(writer)?;
+        self.tags.encode(writer)
+    }
+}
+
+impl Decode for Name {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Self {
+            syllables: Vec::decode(reader)?,
+            phonemes: Vec::decode(reader)?,
+            flags: Vec::decode(reader)?,
+            tags: Vec::decode(reader)?,
+        }
+        .checked()
+    }
+}
+
+/// The fields of a deserialized name, before it is checked as the one decoded from the binary format.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct UncheckedName {
+    syllables: Vec<(Letter, Phonics)>,
+    phonemes: Vec<Vec<Phoneme>>,
+    flags: Vec<SyllableFlags>,
+    tags: Vec<String>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedName> for Name {
+    type Error = NameError;
+
+    fn try_from(fields: UncheckedName) -> Result<Self, NameError> {
+        Self {
+            syllables: fields.syllables,
+            phonemes: fields.phonemes,
+            flags: fields.flags,
+            tags: fields.tags,
+        }
+        .checked()
+    }
+}
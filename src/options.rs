@@ -0,0 +1,276 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::tokenizer::Phoneme;
+use crate::NameError;
+
+/// The default value of `GenerationOptions::syllable_limit`.
+pub const DEFAULT_SYLLABLE_LIMIT: usize = 64;
+
+/// The options for the generation.
+///
+/// Example: GenerationOptions::new().min_syllables(2).max_syllables(3).temperature(1.5).top_p(0.9)
+#[derive(Debug, Clone)]
+pub struct GenerationOptions {
+    pub(crate) min_syllables: usize,
+    pub(crate) max_syllables: Option<usize>,
+    pub(crate) syllable_limit: usize,
+    pub(crate) temperature: f64,
+    pub(crate) top_k: Option<usize>,
+    pub(crate) top_p: Option<f64>,
+    pub(crate) prefix: String,
+    pub(crate) suffix: String,
+    pub(crate) phonemes: Vec<Phoneme>,
+}
+
+impl Default for GenerationOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GenerationOptions {
+    pub fn new() -> Self {
+        Self {
+            min_syllables: 1,
+            max_syllables: None,
+            syllable_limit: DEFAULT_SYLLABLE_LIMIT,
+            temperature: 1.0,
+            top_k: None,
+            top_p: None,
+            prefix: String::new(),
+            suffix: String::new(),
+            phonemes: vec![],
+        }
+    }
+
+    /// Prefer syllables that let the name continue until it has `min_syllables` syllables.
+    pub fn min_syllables(mut self, min_syllables: usize) -> Self {
+        self.min_syllables = min_syllables;
+        self
+    }
+
+    /// Prefer syllables that end the name once it has `max_syllables` syllables.
+    pub fn max_syllables(mut self, max_syllables: usize) -> Self {
+        self.max_syllables = Some(max_syllables);
+        self
+    }
+
+    /// The hard limit of the number of syllables.
+    ///  The generation fails with `NameError::SyllableLimitExceeded` instead of exceeding it.
+    pub fn syllable_limit(mut self, syllable_limit: usize) -> Self {
+        self.syllable_limit = syllable_limit;
+        self
+    }
+
+    /// Reshape the probabilities of the phonetic connections and the syllables by `temperature`.
+    ///  The probabilities are raised to the power of `1 / temperature` and renormalized,
+    ///  so a temperature above 1 makes the names wilder, and below 1 makes them more faithful to the dataset.
+    pub fn temperature(mut self, temperature: f64) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Choose only among the `top_k` most probable candidates at each step.
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    /// Choose only among the most probable candidates whose probabilities sum up to `top_p` at each step.
+    pub fn top_p(mut self, top_p: f64) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    /// Generate only the names starting with the letters `prefix`, ignoring the case.
+    ///  The generation fails with `NameError::UnsatisfiableConstraints` if no such name can be generated.
+    ///
+    /// Example: GenerationOptions::new().starts_with("Ash")
+    pub fn starts_with(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Generate only the names ending with the letters `suffix`, ignoring the case.
+    ///  The generation fails with `NameError::UnsatisfiableConstraints` if no such name can be generated.
+    ///
+    /// Example: GenerationOptions::new().ends_with("ford")
+    pub fn ends_with(mut self, suffix: &str) -> Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+
+    /// Generate only the names containing the sequence of `phonemes`.
+    ///  The generation fails with `NameError::UnsatisfiableConstraints` if no such name can be generated.
+    pub fn contains_phonemes(mut self, phonemes: Vec<Phoneme>) -> Self {
+        self.phonemes = phonemes;
+        self
+    }
+
+    /// Whether the names are constrained by the letters or the phonemes.
+    pub(crate) fn constrains(&self) -> bool {
+        !self.prefix.is_empty() || !self.suffix.is_empty() || !self.phonemes.is_empty()
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), NameError> {
+        if let Some(max_syllables) = self.max_syllables {
+            if max_syllables < self.min_syllables {
+                return Err(NameError::InvalidSyllableRange {
+                    min: self.min_syllables,
+                    max: max_syllables,
+                });
+            }
+        }
+        if !(self.temperature.is_finite() && self.temperature > 0.0) {
+            return Err(NameError::InvalidTemperature(self.temperature));
+        }
+        if let Some(0) = self.top_k {
+            return Err(NameError::InvalidTopK(0));
+        }
+        if let Some(top_p) = self.top_p {
+            if !(top_p > 0.0 && top_p <= 1.0) {
+                return Err(NameError::InvalidTopP(top_p));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the probabilities are reshaped by `reshape`.
+    pub(crate) fn reshapes(&self) -> bool {
+        self.temperature != 1.0 || self.top_k.is_some() || self.top_p.is_some()
+    }
+
+    /// Reshape the probabilities of `candidates` by the temperature, and truncate them to the top-k and top-p ones.
+    ///  The order of the candidates is kept, and the probabilities are renormalized.
+    pub(crate) fn reshape<T>(&self, candidates: Vec<(T, f64)>) -> Vec<(T, f64)> {
+        if !self.reshapes() || candidates.is_empty() {
+            return candidates;
+        }
+        self.truncated(self.tempered(candidates))
+    }
+
+    /// Truncate `candidates` to the top-k and top-p ones, without tempering them.
+    ///  The order of the candidates is kept, and the probabilities are renormalized.
+    pub(crate) fn truncated<T>(&self, candidates: Vec<(T, f64)>) -> Vec<(T, f64)> {
+        if candidates.is_empty() {
+            return candidates;
+        }
+        let (candidates, probabilities): (Vec<_>, Vec<_>) = candidates.into_iter().unzip();
+        let total = probabilities.iter().sum::<f64>();
+
+        let mut ranking = (0..candidates.len()).collect::<Vec<_>>();
+        ranking.sort_by(|a, b| probabilities[*b].total_cmp(&probabilities[*a]));
+        let mut kept = vec![false; candidates.len()];
+        let mut cumulative = 0.0;
+        for (rank, i) in ranking.into_iter().enumerate() {
+            if self.top_k.is_some_and(|top_k| rank >= top_k) {
+                break;
+            }
+            kept[i] = true;
+            cumulative += probabilities[i] / total;
+            if self.top_p.is_some_and(|top_p| cumulative >= top_p) {
+                break;
+            }
+        }
+
+        let kept_total = (0..candidates.len())
+            .filter(|i| kept[*i])
+            .map(|i| probabilities[i])
+            .sum::<f64>();
+        candidates
+            .into_iter()
+            .zip(probabilities)
+            .zip(kept)
+            .filter(|(_, kept)| *kept)
+            .map(|((candidate, p), _)| (candidate, p / kept_total))
+            .collect()
+    }
+
+    /// Reshape the probabilities of `candidates` by the temperature alone, without truncating any of them.
+    ///  The order of the candidates is kept, and the probabilities are renormalized.
+    pub(crate) fn tempered<T>(&self, candidates: Vec<(T, f64)>) -> Vec<(T, f64)> {
+        if self.temperature == 1.0 || candidates.is_empty() {
+            return candidates;
+        }
+        // computed in log-scale not to underflow with a low temperature
+        let logits = candidates
+            .iter()
+            .map(|(_, p)| p.ln() / self.temperature)
+            .collect::<Vec<_>>();
+        let max = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let probabilities = logits.iter().map(|l| (l - max).exp()).collect::<Vec<_>>();
+        let total = probabilities.iter().sum::<f64>();
+        candidates
+            .into_iter()
+            .zip(probabilities)
+            .map(|((candidate, _), p)| (candidate, p / total))
+            .collect()
+    }
+
+    /// Whether a syllable at the position `count` (1-based) is allowed to continue (`to_restore`) or end the name.
+    ///  This is a preference, which is ignored when no syllable satisfies it.
+    pub(crate) fn prefers(&self, count: usize, to_restore: bool) -> bool {
+        if count < self.min_syllables && !to_restore {
+            return false;
+        }
+        if let Some(max_syllables) = self.max_syllables {
+            if count >= max_syllables && to_restore {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// The elements of `list` satisfying `prefers`, or all of them if none does.
+pub(crate) fn preferred<T: Copy>(list: Vec<T>, prefers: impl Fn(&T) -> bool) -> Vec<T> {
+    let filtered = list
+        .iter()
+        .filter(|element| prefers(element))
+        .copied()
+        .collect::<Vec<_>>();
+    if filtered.is_empty() {
+        list
+    } else {
+        filtered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{GenerationOptions, Name, NameGeneratorBuilder};
+
+    #[test]
+    fn top_k_and_top_p_keep_the_most_probable_name() {
+        // `ming` is medial and `mouth` is final after `bir`, so the candidates joined by `m` are of both pools
+        let generator = NameGeneratorBuilder::new()
+            .add_name(Name::new(vec![("bir", "bɜr"), ("ming", "mɪŋ"), ("ham", "həm")]).unwrap())
+            .add_weighted_name(
+                Name::new(vec![("bir", "bɜr"), ("mouth", "məθ")]).unwrap(),
+                2.0,
+            )
+            .build()
+            .unwrap();
+        for options in [
+            GenerationOptions::new().top_k(1),
+            GenerationOptions::new().top_p(0.01),
+            GenerationOptions::new().temperature(2.0).top_k(1),
+        ] {
+            let names = (0..50)
+                .map(|index| {
+                    generator
+                        .generate_seeded(&options, 1, index)
+                        .unwrap()
+                        .content()
+                })
+                .collect::<HashSet<_>>();
+            assert_eq!(names.len(), 1, "{options:?}: {names:?}");
+        }
+    }
+}
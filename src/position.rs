@@ -0,0 +1,226 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Letter, Name, NameGenerator, Phonics, Termination, ToRestore};
+
+/// The position of a syllable in the name.
+///  The syllables continuing the name after the first one form the medial pool,
+///  and the ones ending it form the final pool.
+///  The generation draws the pool of the next syllable by the frequencies of the positions in the dataset,
+///  and then the syllable within it.
+///
+/// Example: Birmingham -> (Bir: Initial) (ming: Medial) (ham: Final)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Position {
+    /// The first syllable of the name
+    Initial,
+    /// The syllable between the first and the last ones
+    Medial,
+    /// The last syllable of the name
+    Final,
+}
+
+impl Position {
+    /// The position of a syllable following another one, which continues the name if `to_restore` is set.
+    pub(crate) fn of_next(to_restore: ToRestore) -> Self {
+        if to_restore {
+            Self::Medial
+        } else {
+            Self::Final
+        }
+    }
+
+    /// The index of the pool of the syllables following another one, which is 0 for the medial and 1 for the final.
+    fn pool(to_restore: ToRestore) -> usize {
+        Self::of_next(to_restore).index() - 1
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Self::Initial => 0,
+            Self::Medial => 1,
+            Self::Final => 2,
+        }
+    }
+}
+
+/// The weighted frequency of a syllable at each position in the dataset.
+///  The syllable of a single-syllable name is counted as both `Initial` and `Final`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PositionFrequency {
+    frequencies: [f64; 3],
+    occurrences: f64,
+}
+
+impl PositionFrequency {
+    /// The total weight of the names in which the syllable is at `position`.
+    pub fn get(&self, position: Position) -> f64 {
+        self.frequencies[position.index()]
+    }
+
+    /// The share of the occurrences of the syllable at `position`, in `[0, 1]`.
+    pub fn share(&self, position: Position) -> f64 {
+        if self.occurrences == 0.0 {
+            return 0.0;
+        }
+        self.get(position) / self.occurrences
+    }
+
+    /// The total weight of the occurrences of the syllable.
+    pub fn occurrences(&self) -> f64 {
+        self.occurrences
+    }
+
+    /// The total frequency of the positions of all the syllables in `names` weighted by `weights`.
+    pub(crate) fn learn(names: &[Name], weights: &[f64]) -> Self {
+        let mut frequency = Self::default();
+        count_positions(names, weights, |_, ipc, len, weight| {
+            frequency.add(ipc, len, weight)
+        });
+        frequency
+    }
+
+    fn add(&mut self, index: usize, len: usize, weight: f64) {
+        if index == 0 {
+            self.frequencies[Position::Initial.index()] += weight;
+        }
+        if index + 1 == len {
+            self.frequencies[Position::Final.index()] += weight;
+        }
+        if index != 0 && index + 1 != len {
+            self.frequencies[Position::Medial.index()] += weight;
+        }
+        self.occurrences += weight;
+    }
+}
+
+impl NameGenerator {
+    /// The frequency of each syllable (letter and phonics) at each position in the dataset.
+    ///  The separators are not counted, and the positions are of the names rather than the words.
+    ///  The frequencies are counted over all the names on each call.
+    pub fn position_frequencies(&self) -> HashMap<&(Letter, Phonics), PositionFrequency> {
+        let mut frequencies = HashMap::<_, PositionFrequency>::new();
+        count_positions(&self.names, &self.weights, |syllable, ipc, len, weight| {
+            frequencies
+                .entry(syllable)
+                .or_default()
+                .add(ipc, len, weight)
+        });
+        frequencies
+    }
+
+    /// The frequency of the syllables whose letter is `letter` at each position in the dataset, ignoring the case.
+    ///  The frequency is counted over all the names on each call.
+    ///
+    /// Example: generator.position_frequency("ford").share(Position::Final)
+    pub fn position_frequency(&self, letter: &str) -> PositionFrequency {
+        let letter = letter.to_lowercase();
+        let mut frequency = PositionFrequency::default();
+        count_positions(&self.names, &self.weights, |syllable, ipc, len, weight| {
+            if syllable.0.to_lowercase() == letter {
+                frequency.add(ipc, len, weight);
+            }
+        });
+        frequency
+    }
+
+    /// The weights of the syllables in `list` following another one in the name, drawn first by the pools of their positions.
+    ///  The medial and the final pools present in `list` share the total weight by the frequencies of their positions in the dataset,
+    ///  and the syllables share the one of their pool by the weights of their names.
+    ///  With `Termination::Learned`, the syllables are weighted by their names alone, regardless of their positions.
+    pub(crate) fn pooled_weights(&self, list: &[&(usize, usize, ToRestore)]) -> Vec<f64> {
+        let weights = list
+            .iter()
+            .map(|(ipn, _, _)| self.weights[*ipn])
+            .collect::<Vec<_>>();
+        if self.termination == Termination::Learned {
+            return weights;
+        }
+        let mut totals = [0.0; 2];
+        for ((_, _, to_restore), weight) in list.iter().zip(&weights) {
+            totals[Position::pool(*to_restore)] += weight;
+        }
+        let mut shares = [Position::Medial, Position::Final].map(|p| self.positions.get(p));
+        for (share, total) in shares.iter_mut().zip(totals) {
+            if total == 0.0 {
+                *share = 0.0;
+            }
+        }
+        let share_total = shares.iter().sum::<f64>();
+        // no syllable at the positions of the present pools, like the separators in a dataset of two syllables at most
+        if share_total == 0.0 {
+            return weights;
+        }
+        list.iter()
+            .zip(weights)
+            .map(|((_, _, to_restore), weight)| {
+                let pool = Position::pool(*to_restore);
+                shares[pool] / share_total * weight / totals[pool]
+            })
+            .collect()
+    }
+}
+
+/// Call `count` with each syllable other than the separators in `names`, its index, the length of its name and the weight of the name.
+fn count_positions<'a>(
+    names: &'a [Name],
+    weights: &[f64],
+    mut count: impl FnMut(&'a (Letter, Phonics), usize, usize, f64),
+) {
+    for (name, weight) in names.iter().zip(weights) {
+        let len = name.syllables().len();
+        for (ipc, syllable) in name.syllables().iter().enumerate() {
+            if !name.flags_of_syllable(ipc).separator {
+                count(syllable, ipc, len, *weight);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GenerationOptions, Name, NameGeneratorBuilder};
+
+    #[test]
+    fn pools_drawn_by_position_frequencies() {
+        // `ming` is medial and `mouth` is final after `bir`, and `ley` makes the medial position frequent
+        let generator = NameGeneratorBuilder::new()
+            .add_name(Name::new(vec![("bir", "bɜr"), ("ming", "mɪŋ"), ("ham", "həm")]).unwrap())
+            .add_weighted_name(
+                Name::new(vec![("bir", "bɜr"), ("mouth", "məθ")]).unwrap(),
+                2.0,
+            )
+            .add_weighted_name(
+                Name::new(vec![("ox", "ɒks"), ("ley", "li"), ("ton", "tən")]).unwrap(),
+                6.0,
+            )
+            .build()
+            .unwrap();
+        let options = GenerationOptions::default();
+        let (mut ming, mut mouth) = (0, 0);
+        for index in 0..2000 {
+            let name = generator
+                .generate_seeded(&options, 1, index)
+                .unwrap()
+                .content();
+            // the final syllable never continues the name
+            assert!(!name.contains("mouth") || name.ends_with("mouth"), "{name}");
+            ming += name.starts_with("birming") as usize;
+            mouth += (name == "birmouth") as usize;
+        }
+        // the medial pool weighs 1 + 6 against 1 + 2 + 6 of the final pool,
+        //  so `ming` follows `bir` at 7/16 rather than 1/3 by the weights of the names
+        let share = ming as f64 / (ming + mouth) as f64;
+        assert!((share - 7.0 / 16.0).abs() < 0.05, "{share}");
+    }
+}
@@ -0,0 +1,108 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::constraint::Steering;
+use crate::draw::{Draw, FnDraw};
+use crate::random::SplitMix64;
+use crate::{GeneratedName, GenerationOptions, NameError, NameGenerator};
+
+/// The options validated and analyzed for a generator, created by `NameGenerator::prepare`.
+///  The constraints are analyzed once here rather than on every generation,
+///  so it is faster to generate many constrained names from it.
+///
+/// Example: let prepared = generator.prepare(&GenerationOptions::new().ends_with("ford"))?;
+///     (0..100).map(|index| prepared.generate_seeded(1234, index))
+pub struct PreparedOptions<'a> {
+    generator: &'a NameGenerator,
+    options: GenerationOptions,
+    // the analysis of the model for the constraints of `options`, if any
+    steering: Option<Steering<'a>>,
+}
+
+impl<'a> PreparedOptions<'a> {
+    /// The analysis of the model for the constraints, if any.
+    pub(crate) fn steering(&self) -> Option<&Steering<'a>> {
+        self.steering.as_ref()
+    }
+
+    /// The options prepared.
+    pub fn options(&self) -> &GenerationOptions {
+        &self.options
+    }
+
+    /// Generate a name, with detailed information of the syllables.
+    /// Random number generator is required as argument `rand_fn`, which must return values in `[0, 1)`.
+    pub fn generate(&self, rand_fn: impl FnMut() -> f64) -> Result<GeneratedName<'a>, NameError> {
+        self.generate_with_draw(&mut FnDraw(rand_fn))
+    }
+
+    /// Generate the `index`-th name of `seed`, which is the same as `NameGenerator::generate_seeded` with the options.
+    pub fn generate_seeded(&self, seed: u64, index: u64) -> Result<GeneratedName<'a>, NameError> {
+        let mut rng = SplitMix64::from_seed_and_index(seed, index);
+        self.generate(|| rng.next_f64())
+    }
+
+    /// Generate a name with `rng`, with detailed information of the syllables.
+    #[cfg(feature = "rand")]
+    pub fn generate_with_rng<R: rand::Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+    ) -> Result<GeneratedName<'a>, NameError> {
+        self.generate_with_draw(&mut crate::draw::RngDraw(rng))
+    }
+
+    /// Generate a name, drawing the random values from `draw`.
+    pub(crate) fn generate_with_draw(
+        &self,
+        draw: &mut dyn Draw,
+    ) -> Result<GeneratedName<'a>, NameError> {
+        self.generator
+            .generate_steered(&self.options, self.steering.as_ref(), draw)
+    }
+}
+
+impl NameGenerator {
+    /// Validate `options`, and analyze the model for its constraints to generate many names under them.
+    ///  An error is returned if the options are invalid or no name satisfies the constraints.
+    pub fn prepare(&self, options: &GenerationOptions) -> Result<PreparedOptions<'_>, NameError> {
+        options.validate()?;
+        if self.incoming_syllables.is_empty() {
+            return Err(NameError::EmptyModel);
+        }
+        let steering = if options.constrains() {
+            Some(Steering::new(self, options)?)
+        } else {
+            None
+        };
+        Ok(PreparedOptions {
+            generator: self,
+            options: options.clone(),
+            steering,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sample_generator, GenerationOptions};
+
+    #[test]
+    fn same_names_as_unprepared() {
+        let generator = sample_generator();
+        let options = GenerationOptions::new().ends_with("ley");
+        let prepared = generator.prepare(&options).unwrap();
+        for index in 0..50 {
+            let name = prepared.generate_seeded(1234, index).unwrap();
+            let expected = generator.generate_seeded(&options, 1234, index).unwrap();
+            assert_eq!(name.content(), expected.content());
+            assert!(name.content().ends_with("ley"));
+            assert_eq!(
+                name.log_probability().to_bits(),
+                expected.log_probability().to_bits()
+            );
+        }
+    }
+}
@@ -0,0 +1,105 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! The built-in random number generator for the reproducible generation.
+//!
+//! The generator is SplitMix64, which is fully specified here so that the same seed produces the same names
+//! on every platform and with every version of the other crates.
+//! - `mix(z)`: `z = (z ^ (z >> 30)) * 0xBF58476D1CE4E5B9; z = (z ^ (z >> 27)) * 0x94D049BB133111EB; z ^ (z >> 31)`
+//! - Next `u64`: `state += 0x9E3779B97F4A7C15`, then `mix(state)`
+//! - Next `f64`: the upper 53 bits of the next `u64`, multiplied by `2^-53`
+//! - The initial state for `(seed, index)`: `mix(seed ^ mix(index))`
+//!
+//! All the arithmetic is wrapping.
+
+const GAMMA: u64 = 0x9E3779B97F4A7C15;
+
+fn mix(z: u64) -> u64 {
+    let z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    let z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// The SplitMix64 random number generator.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(state: u64) -> Self {
+        Self { state }
+    }
+
+    /// Create the generator of the `index`-th stream of `seed`.
+    ///  The streams of the different indices are independent of each other.
+    pub fn from_seed_and_index(seed: u64, index: u64) -> Self {
+        Self::new(mix(seed ^ mix(index)))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(GAMMA);
+        mix(self.state)
+    }
+
+    /// Generate a value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SplitMix64;
+    use crate::{sample_generator, GenerationOptions};
+
+    #[test]
+    fn known_u64() {
+        // the reference outputs of SplitMix64 for the state 1234567
+        let mut rng = SplitMix64::new(1234567);
+        for expected in [
+            6457827717110365317,
+            3203168211198807973,
+            9817491932198370423,
+            4593380528125082431,
+            16408922859458223821,
+        ] {
+            assert_eq!(rng.next_u64(), expected);
+        }
+    }
+
+    #[test]
+    fn known_f64() {
+        let mut rng = SplitMix64::new(0);
+        for expected in [
+            0.8833108082136426,
+            0.43152799704850997,
+            0.026433771592597743,
+        ] {
+            assert_eq!(rng.next_f64(), expected);
+        }
+    }
+
+    #[test]
+    fn known_seed_and_index() {
+        let mut rng = SplitMix64::from_seed_and_index(1234, 42);
+        for expected in [14861241969957533694, 16627632236117734955] {
+            assert_eq!(rng.next_u64(), expected);
+        }
+    }
+
+    #[test]
+    fn known_seeded_name() {
+        let generator = sample_generator();
+        let name = generator
+            .generate_seeded(&GenerationOptions::default(), 1234, 42)
+            .unwrap();
+        assert_eq!(
+            (name.content(), name.script()),
+            ("bradley".to_string(), "brædli".to_string())
+        );
+    }
+}
@@ -0,0 +1,222 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::constraint::{advance, MatchState, Progress, Steering};
+use crate::{
+    GeneratedName, GenerationOptions, NameError, NameGenerator, Step, SyllableInfo, ToRestore,
+};
+
+/// The partial name in the search, ordered by its log-probability.
+struct Node {
+    log_probability: f64,
+    syllable_info: Vec<SyllableInfo>,
+    to_restore: ToRestore,
+    state: MatchState,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.log_probability.total_cmp(&other.log_probability)
+    }
+}
+
+impl NameGenerator {
+    /// Find the `n` most probable names under `options` by the best-first search, in the descending order of the log-probability.
+    ///  The syllables with the same letter, phonics and role are counted together as one candidate,
+    ///  and the names out of the range of `min_syllables` and `max_syllables` are skipped.
+    ///  Fewer names are returned if the model can not produce `n` distinct names within `syllable_limit` syllables.
+    ///  The names violating the constraints of `options` are never explored,
+    ///  and the log-probabilities are conditioned on the constraints.
+    pub fn top_n(
+        &self,
+        n: usize,
+        options: &GenerationOptions,
+    ) -> Result<Vec<GeneratedName<'_>>, NameError> {
+        let prepared = self.prepare(options)?;
+        let steering = prepared.steering();
+        let root = Node {
+            log_probability: 0.0,
+            syllable_info: vec![],
+            to_restore: true,
+            state: MatchState::default(),
+        };
+        let mut heap = BinaryHeap::new();
+        self.expand(&root, 1, options, steering, &mut heap)?;
+
+        let mut names = vec![];
+        let mut found = HashSet::new();
+        while names.len() < n {
+            let Some(node) = heap.pop() else {
+                break;
+            };
+            let count = node.syllable_info.len();
+            if !node.to_restore {
+                if count >= options.min_syllables {
+                    let name = GeneratedName::new(&self.names, node.syllable_info);
+                    if found.insert((name.content(), name.script())) {
+                        names.push(name);
+                    }
+                }
+                continue;
+            }
+            if count >= options.syllable_limit
+                || options.max_syllables.is_some_and(|max| count >= max)
+            {
+                continue;
+            }
+            self.expand(&node, count + 1, options, steering, &mut heap)?;
+        }
+        Ok(names)
+    }
+
+    /// Push the names extending `node` at the position `count` to `heap`,
+    ///  each with the syllable in every role it can take there.
+    fn expand(
+        &self,
+        node: &Node,
+        count: usize,
+        options: &GenerationOptions,
+        steering: Option<&Steering>,
+        heap: &mut BinaryHeap<Node>,
+    ) -> Result<(), NameError> {
+        let progress = steering.map(|steering| (steering, node.state));
+        for (syllable, probability, join) in self.next_syllables(node, count, options, steering)? {
+            for (to_restore, role_probability) in self.roles(syllable, count, options, progress) {
+                let syllable = (syllable.0, syllable.1, to_restore);
+                let info = self.syllable_info(&syllable, probability * role_probability, join);
+                heap.push(node.extend(&syllable, info, steering)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// The syllables that can follow `node` at the position `count`, with their probabilities and the phonemes of the join.
+    ///  The syllables violating the constraints prepared by `steering` are excluded,
+    ///  and the probabilities are conditioned on the constraints.
+    fn next_syllables(
+        &self,
+        node: &Node,
+        count: usize,
+        options: &GenerationOptions,
+        steering: Option<&Steering>,
+    ) -> Result<Vec<Step<'_>>, NameError> {
+        let progress = steering.map(|steering| (steering, node.state));
+        // a name is started, or a new word is started after a separator
+        let separated = node.syllable_info.last().map(|last| {
+            self.names[last.name_index]
+                .flags_of_syllable(last.syllable_index)
+                .separator
+        });
+        if separated != Some(false) {
+            let list = self.starting_list(separated.is_some(), count, options, progress);
+            return Ok(self
+                .grouped(list, separated.is_some(), options, progress)
+                .into_iter()
+                .map(|(syllable, probability)| (syllable, probability, None))
+                .collect());
+        }
+        let last = &node.syllable_info[node.syllable_info.len() - 1];
+        let last = (last.name_index, last.syllable_index);
+        if self.successors(last).is_some() {
+            let candidates = self.interpolated(last, count, options, progress)?;
+            return Ok(self
+                .merged(candidates)
+                .into_iter()
+                .map(|(syllable, probability)| {
+                    (syllable, probability, Some(self.join_key(syllable)))
+                })
+                .collect());
+        }
+        let mut next = vec![];
+        for (key, connection_probability) in self.connections(last, count, options, progress)? {
+            let list = self.outgoing_list(key, count, options, progress)?;
+            for (syllable, probability) in self.grouped(list, true, options, progress) {
+                next.push((syllable, connection_probability * probability, Some(key)));
+            }
+        }
+        Ok(next)
+    }
+
+    /// The distinct syllables in `list` with the probabilities to be chosen, merged as `merged`.
+    ///  The syllables `following` another one in the name are drawn first by the pools of their positions.
+    fn grouped<'a>(
+        &self,
+        list: Vec<&'a (usize, usize, ToRestore)>,
+        following: bool,
+        options: &GenerationOptions,
+        progress: Progress,
+    ) -> Vec<(&'a (usize, usize, ToRestore), f64)> {
+        let weights = self.syllable_weights(&list, following, options, progress);
+        self.merged(list.into_iter().zip(weights).collect())
+    }
+
+    /// The distinct syllables in `candidates` with the probabilities in proportion to the weights.
+    ///  The syllables with the same letter, phonics and role are represented by the first one,
+    ///  where the role to continue the name is ignored with `Termination::Learned`.
+    fn merged<'a>(
+        &self,
+        candidates: Vec<(&'a (usize, usize, ToRestore), f64)>,
+    ) -> Vec<(&'a (usize, usize, ToRestore), f64)> {
+        let total = candidates.iter().map(|(_, weight)| weight).sum::<f64>();
+        let mut groups: Vec<(&(usize, usize, ToRestore), f64)> = vec![];
+        let mut group_of = HashMap::new();
+        for (syllable, weight) in candidates {
+            let (ipn, ipc, to_restore) = *syllable;
+            let flags = self.names[ipn].flags_of_syllable(ipc);
+            let key = (
+                &self.names[ipn].syllables()[ipc],
+                flags.separator,
+                flags.word_final,
+                self.role_key(to_restore),
+            );
+            let group = *group_of.entry(key).or_insert_with(|| {
+                groups.push((syllable, 0.0));
+                groups.len() - 1
+            });
+            groups[group].1 += weight / total;
+        }
+        groups.retain(|(_, probability)| *probability > 0.0);
+        groups
+    }
+}
+
+impl Node {
+    /// The name extended with `syllable` described by `info`.
+    fn extend(
+        &self,
+        syllable: &(usize, usize, ToRestore),
+        info: SyllableInfo,
+        steering: Option<&Steering>,
+    ) -> Result<Self, NameError> {
+        let log_probability = self.log_probability + info.log_probability;
+        let mut syllable_info = self.syllable_info.clone();
+        syllable_info.push(info);
+        Ok(Self {
+            log_probability,
+            syllable_info,
+            to_restore: syllable.2,
+            state: advance(steering.map(|steering| (steering, self.state)), syllable)?
+                .map_or(self.state, |(_, state)| state),
+        })
+    }
+}
@@ -0,0 +1,179 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+//! The serde representation of a built `NameGenerator`.
+//!
+//! The maps keyed by the phonemes or the tuples are written as sequences of pairs,
+//! so that the formats with only string keys like JSON can hold them.
+//! A deserialized generator is validated as the one read by `NameGenerator::load`.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{Letter, Phonics};
+use crate::{
+    Model, Name, NameError, NameGenerator, PhonemeKey, PhoneticConnection, PositionFrequency,
+    Smoothing, StopModel, Termination, ToRestore,
+};
+
+/// The map written as a sequence of `(key, value)` pairs sorted by the keys.
+pub(crate) mod pairs {
+    use std::collections::HashMap;
+    use std::hash::Hash;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize + Ord,
+        V: Serialize,
+        S: Serializer,
+    {
+        // sorted by the keys to write the same output for the same generator
+        let mut entries = map.iter().collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        serializer.collect_seq(entries)
+    }
+
+    pub(crate) fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+/// The fields of a deserialized generator, before it is validated.
+#[derive(Deserialize)]
+pub(crate) struct UncheckedGenerator {
+    names: Vec<Name>,
+    weights: Vec<f64>,
+    datasets: Vec<usize>,
+    incoming_syllables: Vec<(usize, usize, ToRestore)>,
+    word_initial_syllables: Vec<(usize, usize, ToRestore)>,
+    outgoing_syllables: Vec<(usize, usize, ToRestore)>,
+    #[serde(with = "pairs")]
+    outgoing_tree: HashMap<PhonemeKey, Vec<usize>>,
+    conn: PhoneticConnection,
+    final_syllables: Vec<(usize, usize, ToRestore)>,
+    preceding_syllables: Vec<(usize, usize, ToRestore)>,
+    #[serde(with = "pairs")]
+    preceding_tree: HashMap<PhonemeKey, Vec<usize>>,
+    backward_conn: PhoneticConnection,
+    model: Model,
+    #[serde(with = "pairs")]
+    successor_tree: HashMap<(Letter, Phonics), Vec<usize>>,
+    termination: Termination,
+    smoothing: Smoothing,
+}
+
+impl TryFrom<UncheckedGenerator> for NameGenerator {
+    type Error = NameError;
+
+    fn try_from(fields: UncheckedGenerator) -> Result<Self, NameError> {
+        let mut generator = Self {
+            names: fields.names,
+            weights: fields.weights,
+            datasets: fields.datasets,
+            incoming_syllables: fields.incoming_syllables,
+            word_initial_syllables: fields.word_initial_syllables,
+            outgoing_syllables: fields.outgoing_syllables,
+            outgoing_tree: fields.outgoing_tree,
+            conn: fields.conn,
+            final_syllables: fields.final_syllables,
+            preceding_syllables: fields.preceding_syllables,
+            preceding_tree: fields.preceding_tree,
+            backward_conn: fields.backward_conn,
+            model: fields.model,
+            successor_tree: fields.successor_tree,
+            termination: fields.termination,
+            stop: StopModel::default(),
+            positions: PositionFrequency::default(),
+            smoothing: fields.smoothing,
+        };
+        generator.validate()?;
+        generator.learn_statistics();
+        Ok(generator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{sample_generator, GenerationOptions, Name, NameGenerator};
+
+    #[test]
+    fn json_round_trip() {
+        let generator = sample_generator();
+        let json = serde_json::to_string(&generator).unwrap();
+        let loaded: NameGenerator = serde_json::from_str(&json).unwrap();
+        assert_eq!(json, serde_json::to_string(&loaded).unwrap());
+        let options = GenerationOptions::default();
+        for index in 0..100 {
+            assert_eq!(
+                generator
+                    .generate_seeded(&options, 1234, index)
+                    .unwrap()
+                    .content(),
+                loaded
+                    .generate_seeded(&options, 1234, index)
+                    .unwrap()
+                    .content()
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_generator_is_rejected() {
+        let mut json = serde_json::to_value(sample_generator()).unwrap();
+        json["incoming_syllables"][0][0] = 100.into();
+        assert!(serde_json::from_value::<NameGenerator>(json).is_err());
+    }
+
+    #[test]
+    fn inconsistent_name_is_rejected() {
+        let name = Name::new(vec![("bed", "bɛd"), ("ford", "fərd")]).unwrap();
+        let json = serde_json::to_value(&name).unwrap();
+        assert!(serde_json::from_value::<Name>(json.clone()).is_ok());
+        // a syllable without its phonemes
+        let mut broken = json.clone();
+        broken["phonemes"].as_array_mut().unwrap().pop();
+        assert!(serde_json::from_value::<Name>(broken).is_err());
+        // a syllable with phonemes flagged as a separator
+        let mut broken = json;
+        broken["flags"][1]["separator"] = true.into();
+        assert!(serde_json::from_value::<Name>(broken).is_err());
+    }
+
+    #[test]
+    fn unsorted_connection_is_sorted() {
+        let generator = sample_generator();
+        let mut json = serde_json::to_value(&generator).unwrap();
+        let contexts = json["conn"]["conn"]["elements"].as_array_mut().unwrap();
+        contexts.reverse();
+        for context in contexts {
+            context[1]["elements"].as_array_mut().unwrap().reverse();
+        }
+        let loaded = serde_json::from_value::<NameGenerator>(json).unwrap();
+        let options = GenerationOptions::default();
+        for index in 0..100 {
+            assert_eq!(
+                generator
+                    .generate_seeded(&options, 1234, index)
+                    .unwrap()
+                    .content(),
+                loaded
+                    .generate_seeded(&options, 1234, index)
+                    .unwrap()
+                    .content()
+            );
+        }
+    }
+}
@@ -4,6 +4,14 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
 
+use std::io::{Read, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::binary::{Decode, Encode};
+use crate::NameError;
+
 pub struct SortedVecBuilder<C, V>
 where
     C: PartialOrd,
@@ -31,6 +39,8 @@ where
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "UncheckedSortedVec<C, V>"))]
 pub struct SortedVec<C, V>
 where
     C: PartialOrd,
@@ -42,7 +52,9 @@ impl<C, V> SortedVec<C, V>
 where
     C: PartialOrd,
 {
-    pub fn find(&self, cmp: C) -> &(C, V) {
+    /// Find the first element whose key is not less than `cmp`.
+    /// The last element is returned if every key is less than `cmp`, and `None` if the vector is empty.
+    pub fn find(&self, cmp: &C) -> Option<&(C, V)> {
         let mut left: i32 = -1;
         let mut right: i32 = self.elements.len() as i32;
 
@@ -53,7 +65,7 @@ where
                 .get(mid as usize)
                 .unwrap()
                 .0
-                .partial_cmp(&cmp)
+                .partial_cmp(cmp)
                 .unwrap()
             {
                 std::cmp::Ordering::Less => left = mid,
@@ -62,6 +74,75 @@ where
             }
         }
 
-        &self.elements[right as usize]
+        self.elements
+            .get(right as usize)
+            .or_else(|| self.elements.last())
+    }
+
+    /// Get the value whose key is equal to `cmp`.
+    pub fn get(&self, cmp: &C) -> Option<&V> {
+        self.find(cmp)
+            .filter(|(c, _)| c.partial_cmp(cmp) == Some(std::cmp::Ordering::Equal))
+            .map(|(_, v)| v)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(C, V)> {
+        self.elements.iter()
+    }
+
+    /// Sort `elements` read from a file, which is broken if any of the keys can not be compared.
+    fn from_elements(elements: Vec<(C, V)>) -> Result<Self, NameError> {
+        if elements
+            .windows(2)
+            .any(|pair| pair[0].0.partial_cmp(&pair[1].0).is_none())
+        {
+            return Err(crate::binary::invalid("incomparable keys"));
+        }
+        let mut builder = SortedVecBuilder::new();
+        elements.into_iter().for_each(|(c, v)| builder.push(c, v));
+        Ok(builder.build())
+    }
+}
+
+impl<C, V> Encode for SortedVec<C, V>
+where
+    C: PartialOrd + Encode,
+    V: Encode,
+{
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        self.elements.len().encode(writer)?;
+        self.elements.iter().try_for_each(|(c, v)| {
+            c.encode(writer)?;
+            v.encode(writer)
+        })
+    }
+}
+
+impl<C, V> Decode for SortedVec<C, V>
+where
+    C: PartialOrd + Decode,
+    V: Decode,
+{
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Self::from_elements(Vec::decode(reader)?)
+    }
+}
+
+/// The elements of a deserialized `SortedVec`, before they are checked and sorted as the ones decoded.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct UncheckedSortedVec<C, V> {
+    elements: Vec<(C, V)>,
+}
+
+#[cfg(feature = "serde")]
+impl<C, V> TryFrom<UncheckedSortedVec<C, V>> for SortedVec<C, V>
+where
+    C: PartialOrd,
+{
+    type Error = NameError;
+
+    fn try_from(unchecked: UncheckedSortedVec<C, V>) -> Result<Self, NameError> {
+        Self::from_elements(unchecked.elements)
     }
 }
@@ -0,0 +1,367 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::binary::{Decode, Encode};
+use crate::constraint::{completion, Progress};
+use crate::draw::Draw;
+use crate::tokenizer::Phoneme;
+use crate::{GenerationOptions, Name, NameError, NameGenerator, ToRestore};
+
+/// The weight of the estimate by the number of syllables alone in the estimate by the last phoneme,
+///  as the number of the pseudo-occurrences.
+const BACKOFF_WEIGHT: f64 = 2.0;
+
+/// How the generation decides to end the name.
+///
+/// Example: NameGeneratorBuilder::new().termination(Termination::Learned)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Termination {
+    /// The name ends when the chosen syllable was the last one of its name in the dataset.
+    #[default]
+    Structural,
+    /// The name ends after any syllable other than the separators with the probability of `StopModel`,
+    ///  so the numbers of the syllables follow the dataset. The syllables are chosen regardless of their positions.
+    ///  The backward generation always uses the structural termination.
+    Learned,
+}
+
+impl Encode for Termination {
+    fn encode(&self, writer: &mut dyn Write) -> Result<(), NameError> {
+        (*self == Self::Learned).encode(writer)
+    }
+}
+
+impl Decode for Termination {
+    fn decode(reader: &mut dyn Read) -> Result<Self, NameError> {
+        Ok(if bool::decode(reader)? {
+            Self::Learned
+        } else {
+            Self::Structural
+        })
+    }
+}
+
+/// The probability to end the name after a syllable, learned from the dataset.
+///  It is conditioned on the number of the syllables so far and the last phoneme,
+///  and backs off to the number of the syllables alone when the last phoneme is rare at the position.
+///  Every syllable other than the separators is counted, so the separators are counted as syllables but never end the name.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StopModel {
+    // weighted (stops, occurrences) of the syllables other than the separators, by the number of the syllables
+    by_count: Vec<(f64, f64)>,
+    // weighted (stops, occurrences) by the number of the syllables and the last phoneme
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_format::pairs"))]
+    by_phoneme: HashMap<(usize, Phoneme), (f64, f64)>,
+    // weighted (stops, occurrences) by the last phoneme at any number of the syllables
+    by_last_phoneme: HashMap<Phoneme, (f64, f64)>,
+    // weighted number of the names by the number of the syllables
+    lengths: Vec<f64>,
+}
+
+impl StopModel {
+    pub(crate) fn learn(names: &[Name], weights: &[f64]) -> Self {
+        let mut model = Self::default();
+        for (name, weight) in names.iter().zip(weights) {
+            let len = name.syllables().len();
+            if model.lengths.len() < len {
+                model.lengths.resize(len, 0.0);
+                model.by_count.resize(len, (0.0, 0.0));
+            }
+            model.lengths[len - 1] += weight;
+            for ipc in 0..len {
+                if !can_end(name, ipc) {
+                    continue;
+                }
+                let stop = if ipc + 1 == len { *weight } else { 0.0 };
+                let (stops, occurrences) = &mut model.by_count[ipc];
+                *stops += stop;
+                *occurrences += weight;
+                if let Some(phoneme) = name.phonemes_of_syllable(ipc).last() {
+                    for (stops, occurrences) in [
+                        model
+                            .by_phoneme
+                            .entry((ipc + 1, phoneme.clone()))
+                            .or_default(),
+                        model.by_last_phoneme.entry(phoneme.clone()).or_default(),
+                    ] {
+                        *stops += stop;
+                        *occurrences += weight;
+                    }
+                }
+            }
+        }
+        model
+    }
+
+    /// The probability to end the name after its `count`-th syllable (1-based) ending with `last_phoneme`,
+    ///  unless the syllable is a separator. The names longer than any in the dataset always end.
+    ///
+    /// Example: generator.stop_model().probability(2, Some(&"n".to_string()))
+    pub fn probability(&self, count: usize, last_phoneme: Option<&Phoneme>) -> f64 {
+        let by_count = match self.by_count.get(count.wrapping_sub(1)) {
+            Some((stops, occurrences)) if *occurrences > 0.0 => stops / occurrences,
+            _ => return 1.0,
+        };
+        match last_phoneme.and_then(|phoneme| self.by_phoneme.get(&(count, phoneme.clone()))) {
+            Some((stops, occurrences)) => {
+                (stops + BACKOFF_WEIGHT * by_count) / (occurrences + BACKOFF_WEIGHT)
+            }
+            None => by_count,
+        }
+    }
+
+    /// The probability to end the name after a syllable ending with `last_phoneme`, at any number of the syllables.
+    ///  It is used where the number of the syllables is not known.
+    pub(crate) fn marginal(&self, last_phoneme: Option<&Phoneme>) -> f64 {
+        if let Some((stops, occurrences)) =
+            last_phoneme.and_then(|phoneme| self.by_last_phoneme.get(phoneme))
+        {
+            return stops / occurrences;
+        }
+        let (stops, occurrences) = self
+            .by_count
+            .iter()
+            .fold((0.0, 0.0), |(s, o), (stops, occurrences)| {
+                (s + stops, o + occurrences)
+            });
+        stops / occurrences
+    }
+
+    /// The weighted share of the names in the dataset by the number of the syllables,
+    ///  where the index `i` is of the names with `i + 1` syllables.
+    pub fn length_distribution(&self) -> Vec<f64> {
+        let total = self.lengths.iter().sum::<f64>();
+        self.lengths.iter().map(|weight| weight / total).collect()
+    }
+}
+
+/// Whether the name can end after the syllable `ipc` of `name`, which is any syllable other than the separators.
+///  The stops are counted against every such syllable, so the probabilities are the hazards of the lengths in the dataset.
+fn can_end(name: &Name, ipc: usize) -> bool {
+    !name.flags_of_syllable(ipc).separator
+}
+
+impl NameGenerator {
+    /// Get the probability to end the name learned from the dataset, which is used with `Termination::Learned`.
+    pub fn stop_model(&self) -> &StopModel {
+        &self.stop
+    }
+
+    /// The role of `to_restore` that distinguishes the syllables, which is ignored with `Termination::Learned`.
+    pub(crate) fn role_key(&self, to_restore: ToRestore) -> Option<ToRestore> {
+        (self.termination == Termination::Structural).then_some(to_restore)
+    }
+
+    /// Whether `syllable` is preferred at the position `count` by the range of the syllables of `options`.
+    ///  With `Termination::Learned`, any syllable can continue the name, and the syllables that can end it are preferred at the end.
+    pub(crate) fn prefers(
+        &self,
+        options: &GenerationOptions,
+        count: usize,
+        &(ipn, ipc, to_restore): &(usize, usize, ToRestore),
+    ) -> bool {
+        match self.termination {
+            Termination::Structural => options.prefers(count, to_restore),
+            Termination::Learned => {
+                let (can_end, can_continue) = self.possible_roles(&self.names[ipn], ipc);
+                (options.prefers(count, true) || can_end)
+                    && (options.prefers(count, false) || can_continue)
+            }
+        }
+    }
+
+    /// Whether the name can end and be continued after the syllable `ipc` of `name` with `Termination::Learned`.
+    ///  Any syllable other than the separators can end the name, and a syllable can be continued if the phonetic connection from it is known.
+    fn possible_roles(&self, name: &Name, ipc: usize) -> (bool, bool) {
+        (
+            can_end(name, ipc),
+            name.flags_of_syllable(ipc).separator
+                || self.conn.has_incoming(name.phonemes_of_syllable(ipc)),
+        )
+    }
+
+    /// The roles `syllable` can take with their probabilities in the steering, regardless of the position.
+    pub(crate) fn roles_anywhere(
+        &self,
+        &(ipn, ipc, to_restore): &(usize, usize, ToRestore),
+    ) -> Vec<(ToRestore, f64)> {
+        if self.termination == Termination::Structural {
+            return vec![(to_restore, 1.0)];
+        }
+        match self.possible_roles(&self.names[ipn], ipc) {
+            (true, true) => {
+                let stop = self
+                    .stop
+                    .marginal(self.names[ipn].phonemes_of_syllable(ipc).last());
+                vec![(false, stop), (true, 1.0 - stop)]
+            }
+            (can_end, _) => vec![(!can_end, 1.0)],
+        }
+    }
+
+    /// The probability to complete a name satisfying the constraints after `syllable`, which is 1 without constraints.
+    ///  With `Termination::Learned`, it is averaged over the roles of the syllable.
+    pub(crate) fn completion(
+        &self,
+        progress: Progress,
+        syllable: &(usize, usize, ToRestore),
+    ) -> f64 {
+        if progress.is_none() || self.termination == Termination::Structural {
+            return completion(progress, syllable);
+        }
+        self.roles_anywhere(syllable)
+            .into_iter()
+            .map(|(role, probability)| {
+                probability * completion(progress, &(syllable.0, syllable.1, role))
+            })
+            .sum()
+    }
+
+    /// The roles `syllable` at the position `count` can take with their probabilities,
+    ///  conditioned on completing the constraints under `progress` and preferring the range of the syllables of `options`.
+    pub(crate) fn roles(
+        &self,
+        &(ipn, ipc, to_restore): &(usize, usize, ToRestore),
+        count: usize,
+        options: &GenerationOptions,
+        progress: Progress,
+    ) -> Vec<(ToRestore, f64)> {
+        if self.termination == Termination::Structural {
+            return vec![(to_restore, 1.0)];
+        }
+        let (can_end, can_continue) = self.possible_roles(&self.names[ipn], ipc);
+        if !(can_end && can_continue) {
+            return vec![(!can_end, 1.0)];
+        }
+        let stop = if !options.prefers(count, false) {
+            0.0
+        } else if !options.prefers(count, true) {
+            1.0
+        } else {
+            self.stop
+                .probability(count, self.names[ipn].phonemes_of_syllable(ipc).last())
+        };
+        let completions = [false, true].map(|role| completion(progress, &(ipn, ipc, role)));
+        let mut weights = [stop * completions[0], (1.0 - stop) * completions[1]];
+        // the range of the syllables is a preference, which is ignored when the constraints can not be completed under it
+        if weights.iter().sum::<f64>() == 0.0 {
+            weights = completions;
+        }
+        let total = weights.iter().sum::<f64>();
+        [false, true]
+            .into_iter()
+            .zip(weights)
+            .filter(|(_, weight)| *weight > 0.0)
+            .map(|(role, weight)| (role, weight / total))
+            .collect()
+    }
+
+    /// Decide whether the name is continued after `syllable` chosen at the position `count`,
+    ///  returning the syllable with the decided role and the probability of the decision.
+    ///  Nothing is drawn with `Termination::Structural`, where the role of the syllable is kept.
+    pub(crate) fn decide(
+        &self,
+        syllable: &(usize, usize, ToRestore),
+        count: usize,
+        options: &GenerationOptions,
+        progress: Progress,
+        draw: &mut dyn Draw,
+    ) -> Result<((usize, usize, ToRestore), f64), NameError> {
+        if self.termination == Termination::Structural {
+            return Ok((*syllable, 1.0));
+        }
+        let roles = self.roles(syllable, count, options, progress);
+        let weights = roles.iter().map(|(_, p)| *p).collect::<Vec<_>>();
+        let (role, probability) = roles[draw.next_weighted(&weights)?];
+        Ok(((syllable.0, syllable.1, role), probability))
+    }
+
+    /// The probability of the role of the syllable `i` of `name`, which ends the name if it is the last one.
+    ///  It is 1 with `Termination::Structural`, where the role is a part of the syllable.
+    pub(crate) fn role_probability(&self, name: &Name, i: usize) -> f64 {
+        let last = i + 1 == name.syllables().len();
+        if self.termination == Termination::Structural {
+            return 1.0;
+        }
+        match self.possible_roles(name, i) {
+            (true, true) => {}
+            (can_end, _) => return if last == can_end { 1.0 } else { 0.0 },
+        }
+        let stop = self
+            .stop
+            .probability(i + 1, name.phonemes_of_syllable(i).last());
+        if last {
+            stop
+        } else {
+            1.0 - stop
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GenerationOptions, Name, NameGeneratorBuilder, Termination};
+
+    #[test]
+    fn learned_lengths_follow_dataset() {
+        // every syllable ends with `a`, which can be followed by any of them
+        let names = [
+            ("ba", 2.0),
+            ("la", 1.0),
+            ("bana", 3.0),
+            ("mala", 2.0),
+            ("lama", 1.0),
+            ("banala", 2.0),
+            ("malana", 1.0),
+            ("banamala", 1.0),
+        ];
+        let generator = NameGeneratorBuilder::new()
+            .termination(Termination::Learned)
+            .bulk_add_weighted_names(
+                names
+                    .iter()
+                    .map(|(name, weight)| {
+                        let syllables = name
+                            .as_bytes()
+                            .chunks(2)
+                            .map(|s| std::str::from_utf8(s).unwrap())
+                            .map(|s| (s, s))
+                            .collect();
+                        (Name::new(syllables).unwrap(), *weight)
+                    })
+                    .collect(),
+            )
+            .build()
+            .unwrap();
+        let expected = generator.stop_model().length_distribution();
+        let options = GenerationOptions::default();
+        let samples = 4000;
+        let mut histogram = vec![0.0; expected.len()];
+        for index in 0..samples {
+            let len = generator
+                .generate_seeded(&options, 1, index)
+                .unwrap()
+                .syllable_info()
+                .len();
+            if histogram.len() < len {
+                histogram.resize(len, 0.0);
+            }
+            histogram[len - 1] += 1.0 / samples as f64;
+        }
+        for (i, share) in histogram.iter().enumerate() {
+            let expected = expected.get(i).copied().unwrap_or(0.0);
+            assert!((share - expected).abs() < 0.03, "{histogram:?}");
+        }
+    }
+}
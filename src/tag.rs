@@ -0,0 +1,115 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use crate::{NameError, NameGenerator, NameGeneratorBuilder};
+
+/// The default back-off of `NameGenerator::conditioned_with`, used by `NameGenerator::conditioned`.
+///  A tag held by 5 names is weighted as much as all the names, and one held by 45 names takes 90% of the statistics,
+///  so a tag of a handful of names already changes the generated names while still borrowing the syllables of the others.
+pub const DEFAULT_TAG_BACKOFF: f64 = 5.0;
+
+impl NameGenerator {
+    /// Build the generator conditioned on `tags` with `DEFAULT_TAG_BACKOFF`, as `conditioned_with`.
+    ///
+    /// Example: generator.conditioned(&["river"])?.generate(|| rng.gen())
+    pub fn conditioned(&self, tags: &[&str]) -> Result<NameGenerator, NameError> {
+        self.conditioned_with(tags, DEFAULT_TAG_BACKOFF)
+    }
+
+    /// Build the generator conditioned on `tags`, which generates the names like the ones having all of `tags`.
+    ///  The statistics of the tagged names alone are interpolated with the ones of all the names by the weight
+    ///  `λ = count / (count + backoff)` for `count` tagged names, keeping the total weight of the names.
+    ///  Since the tagged names are also among all the names, they take the share `λ + (1 - λ) * tagged / total` of the total weight,
+    ///  where `tagged` and `total` are the weights of the tagged names and all the names.
+    ///  So `backoff` is the number of the tagged names at which both statistics are interpolated equally,
+    ///  and a smaller one conditions more strongly, while a larger one keeps a sparse tag yielding varied names.
+    ///  The generator is the same as this one if no name has all of `tags`,
+    ///  and `NameError::InvalidTagBackoff` is returned if `backoff` is not positive and finite.
+    ///  The names are cloned and the whole model is built again on each call, so the result should be kept for the tags.
+    ///
+    /// Example: generator.conditioned_with(&["river"], 2.0)?.generate(|| rng.gen())
+    pub fn conditioned_with(
+        &self,
+        tags: &[&str],
+        backoff: f64,
+    ) -> Result<NameGenerator, NameError> {
+        if !(backoff.is_finite() && backoff > 0.0) {
+            return Err(NameError::InvalidTagBackoff(backoff));
+        }
+        let tagged = self
+            .names
+            .iter()
+            .map(|name| name.has_tags(tags))
+            .collect::<Vec<_>>();
+        let count = tagged.iter().filter(|tagged| **tagged).count() as f64;
+        let total = self.weights.iter().sum::<f64>();
+        let tagged_total = self
+            .weights
+            .iter()
+            .zip(&tagged)
+            .filter(|(_, tagged)| **tagged)
+            .map(|(weight, _)| weight)
+            .sum::<f64>();
+        // the interpolation weight of the statistics of the tagged names alone, keeping the total weight
+        let lambda = count / (count + backoff);
+        let weights = self
+            .weights
+            .iter()
+            .zip(&tagged)
+            .map(|(weight, tagged)| {
+                if *tagged {
+                    weight * (1.0 - lambda + lambda * total / tagged_total)
+                } else {
+                    weight * (1.0 - lambda)
+                }
+            })
+            .collect();
+
+        let mut builder = NameGeneratorBuilder::new()
+            .context_order(self.conn.order())
+            .smoothing(self.smoothing)
+            .model(self.model)
+            .termination(self.termination);
+        builder.names = self.names.clone();
+        builder.weights = weights;
+        builder.datasets = self.datasets.clone();
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Name, NameError, NameGeneratorBuilder};
+
+    #[test]
+    fn backoff_sets_the_share_of_the_tagged_names() {
+        let generator = NameGeneratorBuilder::new()
+            .add_name(
+                Name::new(vec![("ox", "ɒks"), ("ford", "fərd")])
+                    .unwrap()
+                    .with_tags(&["river"]),
+            )
+            .add_name(
+                Name::new(vec![("brad", "bræd"), ("ford", "fərd")])
+                    .unwrap()
+                    .with_tags(&["river"]),
+            )
+            .add_name(Name::new(vec![("brad", "bræd"), ("ley", "li")]).unwrap())
+            .build()
+            .unwrap();
+        // λ = 2 / (2 + 2), so the tagged names take the share 1/2 + 1/2 * 2/3 = 2.5/3 of the total weight 3
+        let conditioned = generator.conditioned_with(&["river"], 2.0).unwrap();
+        assert_eq!(conditioned.weights(), &vec![1.25, 1.25, 0.5]);
+        let weak = generator.conditioned(&["river"]).unwrap();
+        assert!(weak.weights()[2] > conditioned.weights()[2]);
+        for backoff in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+            assert!(matches!(
+                generator.conditioned_with(&["river"], backoff),
+                Err(NameError::InvalidTagBackoff(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+/// The unit of the phonetic representation, such as `a`, `tʃ` or `ɑː`.
+pub type Phoneme = String;
+
+/// The splitter of the phonetic representation into phonemes.
+///  Phonetic connections are computed between the phonemes at the edges of the syllables.
+pub trait Tokenizer {
+    /// Split `phonics` into phonemes. Characters that should not take part in the connections may be dropped.
+    fn tokenize(&self, phonics: &str) -> Vec<Phoneme>;
+}
+
+/// The tokenizer that treats every character as a phoneme.
+///  This suits the romanized scripts like `sapporo`, and is used by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharTokenizer;
+
+impl Tokenizer for CharTokenizer {
+    fn tokenize(&self, phonics: &str) -> Vec<Phoneme> {
+        phonics.chars().map(|c| c.to_string()).collect()
+    }
+}
+
+/// The tokenizer for the International Phonetic Alphabet.
+///  - Stress markers (`ˈ`, `ˌ`) are ignored.
+///  - Length marks (`ː`), modifier letters (`ʰ`, `ʳ`) and combining diacritics are grouped with the preceding phoneme.
+///  - Affricates (`tʃ`, `dʒ`, `tɕ`, `dʑ`, and any pair joined by a tie bar like `t͡s`) are grouped into a phoneme.
+///
+/// Example: `ˈtʃɑːtəm` -> [`tʃ`, `ɑː`, `t`, `ə`, `m`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IpaTokenizer;
+
+/// The primary stress marker, which marks a syllable as stressed.
+pub(crate) const STRESS_MARKER: char = 'ˈ';
+const STRESS_MARKERS: [char; 2] = [STRESS_MARKER, 'ˌ'];
+const TIE_BARS: [char; 2] = ['\u{0361}', '\u{035C}'];
+const AFFRICATES: [(&str, char); 4] = [("t", 'ʃ'), ("d", 'ʒ'), ("t", 'ɕ'), ("d", 'ʑ')];
+
+/// Whether `c` modifies the preceding character rather than being a phoneme by itself.
+fn is_modifier(c: char) -> bool {
+    matches!(c,
+        '\u{02B0}'..='\u{02FF}' // spacing modifier letters, including `ː`
+        | '\u{0300}'..='\u{036F}' // combining diacritical marks
+        | '\u{1D2C}'..='\u{1D6A}' // phonetic modifier letters
+        | '\u{207F}')
+}
+
+impl Tokenizer for IpaTokenizer {
+    fn tokenize(&self, phonics: &str) -> Vec<Phoneme> {
+        let mut phonemes: Vec<Phoneme> = vec![];
+        for c in phonics.chars() {
+            if STRESS_MARKERS.contains(&c) {
+                continue;
+            }
+            if let Some(last) = phonemes.last_mut() {
+                let tied = last.ends_with(TIE_BARS);
+                let affricate = AFFRICATES
+                    .iter()
+                    .any(|(first, second)| last == first && c == *second);
+                if tied || affricate || is_modifier(c) {
+                    last.push(c);
+                    continue;
+                }
+            }
+            phonemes.push(c.to_string());
+        }
+        phonemes
+    }
+}
+
+/// The class of a phoneme by its manner of articulation, to which the smoothing backs off from the phoneme.
+///  The class is decided by the first character, so it suits both the IPA and the romanized scripts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum PhonemeClass {
+    Vowel,
+    Plosive,
+    Nasal,
+    Fricative,
+    Approximant,
+    Other,
+}
+
+impl PhonemeClass {
+    pub(crate) fn of(phoneme: &str) -> Self {
+        let Some(c) = phoneme.chars().next() else {
+            return Self::Other;
+        };
+        match c.to_lowercase().next().unwrap_or(c) {
+            'a' | 'e' | 'i' | 'o' | 'u' | 'y' | 'æ' | 'ɐ' | 'ɑ' | 'ɒ' | 'ə' | 'ɘ' | 'ɚ' | 'ɛ'
+            | 'ɜ' | 'ɝ' | 'ɞ' | 'ɤ' | 'ɨ' | 'ɪ' | 'ɯ' | 'ɵ' | 'ɶ' | 'ʉ' | 'ʊ' | 'ʌ' | 'ʏ' | 'ø'
+            | 'œ' => Self::Vowel,
+            'p' | 'b' | 't' | 'd' | 'k' | 'g' | 'q' | 'c' | 'ɡ' | 'ʈ' | 'ɖ' | 'ɟ' | 'ɢ' | 'ʔ' => {
+                Self::Plosive
+            }
+            'm' | 'n' | 'ɱ' | 'ɳ' | 'ɲ' | 'ŋ' | 'ɴ' => Self::Nasal,
+            'f' | 'v' | 's' | 'z' | 'h' | 'x' | 'θ' | 'ð' | 'ʃ' | 'ʒ' | 'ɕ' | 'ʑ' | 'ç' | 'ʝ'
+            | 'ɣ' | 'χ' | 'ʁ' | 'ħ' | 'ʕ' | 'ɦ' | 'ɸ' | 'β' | 'ʂ' | 'ʐ' => {
+                Self::Fricative
+            }
+            'l' | 'r' | 'w' | 'j' | 'ɹ' | 'ɾ' | 'ɻ' | 'ɭ' | 'ʎ' | 'ʟ' | 'ɫ' | 'ʀ' | 'ɰ' | 'ʋ'
+            | 'ɥ' | 'ɺ' => Self::Approximant,
+            _ => Self::Other,
+        }
+    }
+}